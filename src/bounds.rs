@@ -0,0 +1,179 @@
+use glam::{Mat4, Vec3, Vec4, Vec4Swizzles};
+
+use crate::vertex::Vertex;
+
+// axis aligned bounding box
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Aabb{
+    pub min: Vec3,
+    pub max: Vec3,
+}
+
+impl Aabb{
+    pub fn from_vertices(vertices: &[Vertex]) -> Self{
+        if vertices.is_empty(){
+            return Aabb { min: Vec3::ZERO, max: Vec3::ZERO };
+        }
+
+        vertices.iter().fold(Aabb { min: Vec3::splat(f32::MAX), max: Vec3::splat(f32::MIN) }, |aabb, vertex| Aabb{
+            min: aabb.min.min(vertex.position),
+            max: aabb.max.max(vertex.position),
+        })
+    }
+
+    pub fn center(&self) -> Vec3{
+        (self.min + self.max) * 0.5
+    }
+
+    // box around the transformed box (Arvo's method), so it can grow with rotations
+    pub fn transformed(&self, matrix: Mat4) -> Self{
+        let center = matrix.transform_point3(self.center());
+        let extents = (self.max - self.min) * 0.5;
+
+        let absolute = |axis: Vec4| axis.xyz().abs();
+        let new_extents = absolute(matrix.x_axis) * extents.x + absolute(matrix.y_axis) * extents.y + absolute(matrix.z_axis) * extents.z;
+
+        Aabb { min: center - new_extents, max: center + new_extents }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct BoundingSphere{
+    pub center: Vec3,
+    pub radius: f32,
+}
+
+impl BoundingSphere{
+    // centered on the box, not the smallest sphere but close enough for culling
+    pub fn from_vertices(vertices: &[Vertex], aabb: &Aabb) -> Self{
+        let center = aabb.center();
+        let radius = vertices.iter().fold(0f32, |radius, vertex| radius.max(vertex.position.distance(center)));
+        BoundingSphere { center, radius }
+    }
+
+    // the radius is scaled by the largest scale of the matrix
+    pub fn transformed(&self, matrix: Mat4) -> Self{
+        let scale = matrix.x_axis.xyz().length().max(matrix.y_axis.xyz().length()).max(matrix.z_axis.xyz().length());
+        BoundingSphere { center: matrix.transform_point3(self.center), radius: self.radius * scale }
+    }
+
+    // smallest sphere containing both
+    pub fn merged(&self, other: &BoundingSphere) -> Self{
+        let distance = self.center.distance(other.center);
+        if distance + other.radius <= self.radius{
+            return *self;
+        }
+        if distance + self.radius <= other.radius{
+            return *other;
+        }
+
+        let radius = (distance + self.radius + other.radius) * 0.5;
+        let center = self.center + (other.center - self.center) * ((radius - self.radius) / distance);
+        BoundingSphere { center, radius }
+    }
+}
+
+// The six planes (xyz the normal pointing inside, w the distance) of a view projection matrix
+pub struct Frustum{
+    pub planes: [Vec4; 6],
+}
+
+impl Frustum{
+    // Gribb/Hartmann: each plane is the sum or difference of the last row and another row of the matrix
+    pub fn from_matrix(view_projection: Mat4) -> Self{
+        let row = |i| view_projection.row(i);
+        let planes = [
+            row(3) + row(0), // left
+            row(3) - row(0), // right
+            row(3) + row(1), // bottom
+            row(3) - row(1), // top
+            row(3) + row(2), // near
+            row(3) - row(2), // far
+        ].map(|plane| plane / plane.xyz().length());
+
+        Frustum { planes }
+    }
+
+    pub fn intersects_sphere(&self, sphere: &BoundingSphere) -> bool{
+        self.planes.iter().all(|plane| plane.xyz().dot(sphere.center) + plane.w >= -sphere.radius)
+    }
+
+    // false only when the box is completely behind one of the planes
+    pub fn intersects_aabb(&self, aabb: &Aabb) -> bool{
+        self.planes.iter().all(|plane| {
+            // the corner furthest along the normal
+            let normal = plane.xyz();
+            let corner = Vec3::select(normal.cmpge(Vec3::ZERO), aabb.max, aabb.min);
+            normal.dot(corner) + plane.w >= 0.
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests{
+    use glam::{vec3, Quat};
+
+    use super::*;
+
+    // at the origin looking down -z, 90 degrees wide: the sides are at |x| = -z and |y| = -z
+    fn frustum() -> Frustum{
+        let projection = Mat4::perspective_rh_gl(90f32.to_radians(), 1., 0.1, 100.);
+        let view = Mat4::look_at_rh(Vec3::ZERO, Vec3::NEG_Z, Vec3::Y);
+        Frustum::from_matrix(projection * view)
+    }
+
+    fn aabb(center: Vec3, half_size: f32) -> Aabb{
+        Aabb { min: center - half_size, max: center + half_size }
+    }
+
+    #[test]
+    fn box_inside_the_frustum(){
+        assert!(frustum().intersects_aabb(&aabb(vec3(0., 0., -5.), 1.)));
+        assert!(frustum().intersects_sphere(&BoundingSphere { center: vec3(0., 0., -5.), radius: 1. }));
+    }
+
+    #[test]
+    fn box_outside_the_frustum(){
+        let frustum = frustum();
+        // behind the camera, past the far plane and off to the left
+        for center in [vec3(0., 0., 5.), vec3(0., 0., -150.), vec3(-20., 0., -5.)]{
+            assert!(!frustum.intersects_aabb(&aabb(center, 1.)), "{}", center);
+            assert!(!frustum.intersects_sphere(&BoundingSphere { center, radius: 1. }), "{}", center);
+        }
+    }
+
+    #[test]
+    fn box_straddling_the_frustum(){
+        let frustum = frustum();
+        // across the left side, the near plane and the far plane
+        for center in [vec3(-5., 0., -5.), vec3(0., 0., 0.), vec3(0., 0., -100.)]{
+            assert!(frustum.intersects_aabb(&aabb(center, 1.)), "{}", center);
+            assert!(frustum.intersects_sphere(&BoundingSphere { center, radius: 1. }), "{}", center);
+        }
+    }
+
+    #[test]
+    fn transformed_box_contains_the_rotated_corners(){
+        let matrix = Mat4::from_scale_rotation_translation(Vec3::splat(2.), Quat::from_rotation_y(45f32.to_radians()), vec3(1., 2., 3.));
+        let transformed = aabb(Vec3::ZERO, 1.).transformed(matrix);
+
+        let half_diagonal = 2. * 2f32.sqrt();
+        assert!(transformed.min.abs_diff_eq(vec3(1. - half_diagonal, 0., 3. - half_diagonal), 1e-5), "{:?}", transformed);
+        assert!(transformed.max.abs_diff_eq(vec3(1. + half_diagonal, 4., 3. + half_diagonal), 1e-5), "{:?}", transformed);
+    }
+
+    #[test]
+    fn merged_spheres(){
+        let a = BoundingSphere { center: vec3(-2., 0., 0.), radius: 1. };
+        let b = BoundingSphere { center: vec3(3., 0., 0.), radius: 2. };
+        let merged = a.merged(&b);
+        assert!(merged.center.abs_diff_eq(vec3(1., 0., 0.), 1e-5), "{:?}", merged);
+        assert!((merged.radius - 4.).abs() < 1e-5, "{:?}", merged);
+        assert_eq!(b.merged(&a), merged);
+
+        // one inside the other is just the outer one
+        let inner = BoundingSphere { center: vec3(3.5, 0., 0.), radius: 0.5 };
+        assert_eq!(b.merged(&inner), b);
+        assert_eq!(inner.merged(&b), b);
+    }
+}
@@ -1,6 +1,6 @@
 use std::collections::HashMap;
 
-use glam::{vec3, Mat4, Vec3};
+use glam::{vec3, Mat4, Vec2, Vec3};
 use glfw::{Action, Key};
 
 pub static mut PROJ_MATRIX: Mat4 = Mat4::IDENTITY;
@@ -149,6 +149,30 @@ impl Camera{
         self.right = Vec3::normalize(Vec3::cross(self.front, world_up));
         self.up = Vec3::normalize(Vec3::cross(self.right, self.front));
     }    
+
+    // direction (world space) of the ray going from the camera through a pixel of the screen,
+    // built from the same fov (zoom) and aspect ratio used by the projection matrix
+    pub fn screen_ray(&self, screen_pos: Vec2, w: f32, h: f32) -> Vec3{
+        let ndc_x = 2. * screen_pos.x / w - 1.;
+        let ndc_y = 1. - 2. * screen_pos.y / h;
+
+        let tan_half_fov = (self.zoom.to_radians() / 2.).tan();
+        let aspect_ratio = w / h;
+
+        let right = self.front.cross(self.up).normalize();
+        let up = right.cross(self.front).normalize();
+
+        (self.front + right * ndc_x * tan_half_fov * aspect_ratio + up * ndc_y * tan_half_fov).normalize()
+    }
+
+    // point under the cursor on the camera-facing plane that goes through `plane_point`,
+    // so something dragged on it keeps the same depth it had when it was grabbed
+    pub fn drag_plane_point(&self, screen_pos: Vec2, w: f32, h: f32, plane_point: Vec3) -> Vec3{
+        let ray = self.screen_ray(screen_pos, w, h);
+        let t = (plane_point - self.position).dot(self.front) / ray.dot(self.front);
+
+        self.position + ray * t
+    }
 }
 
 pub fn create_perspective_projection_matrix(w: f32, h: f32, fov: f32) -> Mat4 {
@@ -159,3 +183,42 @@ pub fn create_perspective_projection_matrix(w: f32, h: f32, fov: f32) -> Mat4 {
 
     Mat4::perspective_rh_gl(fov_y, aspect_ratio, near, far)
 }
+
+#[cfg(test)]
+mod tests{
+    use glam::vec2;
+
+    use super::*;
+
+    #[test]
+    fn ray_through_the_screen_center_is_front(){
+        let mut camera = Camera::new();
+        (camera.yaw, camera.pitch) = (30., -20.);
+        camera.update_camera_vectors();
+
+        let ray = camera.screen_ray(vec2(400., 300.), 800., 600.);
+        assert!(ray.abs_diff_eq(camera.front, 1e-5), "{} {}", ray, camera.front);
+    }
+
+    #[test]
+    fn drag_point_keeps_the_plane_depth(){
+        let mut camera = Camera::new();
+        camera.position = vec3(1., 2., 3.);
+        (camera.yaw, camera.pitch) = (-60., 25.);
+        camera.zoom = 70.;
+        camera.update_camera_vectors();
+
+        let plane_point = camera.position + camera.front * 5. + vec3(0.5, -0.3, 0.2);
+        let depth = (plane_point - camera.position).dot(camera.front);
+        let view_projection = create_perspective_projection_matrix(800., 600., camera.zoom.to_radians())
+            * Mat4::look_at_rh(camera.position, camera.position + camera.front, camera.up);
+        for cursor in [vec2(0., 0.), vec2(400., 300.), vec2(730., 120.), vec2(800., 600.)]{
+            let point = camera.drag_plane_point(cursor, 800., 600., plane_point);
+            assert!(((point - camera.position).dot(camera.front) - depth).abs() < 1e-4, "{} at {}", point, cursor);
+            // and is drawn right under the cursor
+            let ndc = view_projection.project_point3(point);
+            let screen = vec2((ndc.x + 1.) * 400., (1. - ndc.y) * 300.);
+            assert!(screen.abs_diff_eq(cursor, 1e-2), "{} drawn at {}", cursor, screen);
+        }
+    }
+}
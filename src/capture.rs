@@ -0,0 +1,113 @@
+use std::{fs, path::{Path, PathBuf}, time::{SystemTime, UNIX_EPOCH}};
+
+use gl::*;
+use image::{imageops, ImageResult, RgbaImage};
+
+// Reads back what was drawn to the window this frame. Has to happen before the buffers
+// are swapped (Window::update), after everything was drawn (Window::end_scene).
+pub fn read_pixels(width: u32, height: u32) -> RgbaImage{
+    read_framebuffer(0, width, height)
+}
+
+// the color of a single sampled framebuffer object, 0 being the window (its back buffer)
+pub fn read_framebuffer(fbo: u32, width: u32, height: u32) -> RgbaImage{
+    let mut pixels = vec![0u8; (width * height * 4) as usize];
+    unsafe{
+        BindFramebuffer(READ_FRAMEBUFFER, fbo);
+        // rows are tightly packed, whatever the width
+        PixelStorei(PACK_ALIGNMENT, 1);
+        ReadPixels(0, 0, width as i32, height as i32, RGBA, UNSIGNED_BYTE, pixels.as_mut_ptr() as *mut _);
+        BindFramebuffer(READ_FRAMEBUFFER, 0);
+    }
+
+    let mut image = RgbaImage::from_raw(width, height, pixels).expect("pixel buffer has the size of the image");
+    // GL rows go bottom to top
+    imageops::flip_vertical_in_place(&mut image);
+    image
+}
+
+// writes the window to `directory`/screenshot_<unix time in ms>.png and returns the path
+pub fn save_screenshot(directory: &Path, width: u32, height: u32) -> ImageResult<PathBuf>{
+    fs::create_dir_all(directory)?;
+    let millis = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |time| time.as_millis());
+    let path = directory.join(format!("screenshot_{}.png", millis));
+
+    read_pixels(width, height).save(&path)?;
+    Ok(path)
+}
+
+// Saves every `every_nth` frame as a numbered PNG while recording. The simulation should advance
+// by `fixed_timestep` instead of the real frame time meanwhile (see Window::fixed_timestep), so the
+// frames are evenly spaced however slow saving them is, making a video at 1 / (fixed_timestep * every_nth) fps.
+pub struct FrameRecorder{
+    pub directory: PathBuf,
+    pub every_nth: u32,
+    // simulated seconds per frame while recording
+    pub fixed_timestep: f32,
+
+    recording: bool,
+    frame: u64,
+    saved: u64,
+}
+
+impl FrameRecorder{
+    pub fn new(directory: impl Into<PathBuf>, every_nth: u32, fixed_timestep: f32) -> Self{
+        FrameRecorder{
+            directory: directory.into(),
+            every_nth: every_nth.max(1),
+            fixed_timestep,
+
+            recording: false,
+            frame: 0,
+            saved: 0,
+        }
+    }
+
+    pub fn is_recording(&self) -> bool{
+        self.recording
+    }
+
+    // a new recording starts numbering from 0 again, overwriting the frames of the previous one
+    pub fn start(&mut self){
+        self.recording = true;
+        self.frame = 0;
+        self.saved = 0;
+    }
+
+    pub fn stop(&mut self){
+        self.recording = false;
+    }
+
+    pub fn toggle(&mut self){
+        if self.recording{
+            self.stop();
+        }
+        else{
+            self.start();
+        }
+    }
+
+    // what Window::fixed_timestep should be: the fixed one while recording, the real frame time otherwise
+    pub fn timestep(&self) -> Option<f32>{
+        self.recording.then_some(self.fixed_timestep)
+    }
+
+    // Call once per frame at the same point as read_pixels. Returns the path when this frame was saved.
+    pub fn capture(&mut self, width: u32, height: u32) -> ImageResult<Option<PathBuf>>{
+        if !self.recording{
+            return Ok(None);
+        }
+
+        let frame = self.frame;
+        self.frame += 1;
+        if !frame.is_multiple_of(self.every_nth as u64){
+            return Ok(None);
+        }
+
+        fs::create_dir_all(&self.directory)?;
+        let path = self.directory.join(format!("frame_{:06}.png", self.saved));
+        read_pixels(width, height).save(&path)?;
+        self.saved += 1;
+        Ok(Some(path))
+    }
+}
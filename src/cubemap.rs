@@ -0,0 +1,119 @@
+use std::f32::consts::PI;
+
+use gl::{*, types::*};
+use glam::{vec3, Vec3};
+use image::{error::{ParameterError, ParameterErrorKind}, ImageError, ImageResult, Rgb32FImage};
+
+// owns a GL cube map texture, deleted from the GPU when dropped. Used by the Skybox and as the
+// scene environment (see Scene::environment) that Pbr materials reflect.
+pub struct Cubemap{
+    pub id: u32,
+    // width and height of every face
+    pub size: u32,
+}
+
+impl Drop for Cubemap{
+    fn drop(&mut self){
+        unsafe{
+            DeleteTextures(1, &self.id);
+        }
+    }
+}
+
+impl Cubemap{
+    // Six square images of the same size in GL order: +x, -x, +y, -y, +z, -z (right, left, top,
+    // bottom, front, back), not flipped
+    pub fn from_faces(paths: [&str; 6]) -> ImageResult<Self>{
+        let mut faces = Vec::with_capacity(6);
+        for path in paths{
+            let face = image::open(path)?.to_rgb32f();
+            if face.width() != face.height() || faces.first().is_some_and(|first: &Rgb32FImage| first.dimensions() != face.dimensions()){
+                let message = format!("cube map face {} is {}x{}, every face has to be square and the same size", path, face.width(), face.height());
+                return Err(ImageError::Parameter(ParameterError::from_kind(ParameterErrorKind::Generic(message))));
+            }
+            faces.push(face);
+        }
+
+        let size = faces[0].width();
+        let faces: Vec<Vec<f32>> = faces.into_iter().map(|face| face.into_raw()).collect();
+        Ok(Self::upload(size, &faces))
+    }
+
+    // A panorama (2:1, like .hdr environments) resampled into faces of `size` pixels. Wraps
+    // around the same way the equirectangular maps of pbr_shader.fs used to.
+    pub fn from_equirectangular(path: &str, size: u32) -> ImageResult<Self>{
+        let panorama = image::open(path)?.to_rgb32f();
+
+        let faces: Vec<Vec<f32>> = (0..6).map(|face| {
+            let mut pixels = Vec::with_capacity((size * size * 3) as usize);
+            for y in 0..size{
+                for x in 0..size{
+                    let direction = face_direction(face, (x as f32 + 0.5) / size as f32 * 2. - 1., (y as f32 + 0.5) / size as f32 * 2. - 1.);
+                    pixels.extend_from_slice(&sample_panorama(&panorama, direction.normalize()));
+                }
+            }
+            pixels
+        }).collect();
+
+        Ok(Self::upload(size, &faces))
+    }
+
+    // floating point, so HDR images keep their range; with mipmaps for the blurrier reflections,
+    // which need TEXTURE_CUBE_MAP_SEAMLESS (enabled by Window::new) so the face edges don't show
+    fn upload(size: u32, faces: &[Vec<f32>]) -> Self{
+        let mut id = 0;
+        unsafe{
+            GenTextures(1, &mut id);
+            BindTexture(TEXTURE_CUBE_MAP, id);
+            PixelStorei(UNPACK_ALIGNMENT, 1);
+            for (i, face) in faces.iter().enumerate(){
+                TexImage2D(TEXTURE_CUBE_MAP_POSITIVE_X + i as GLenum, 0, RGB16F as GLint, size as GLsizei, size as GLsizei, 0, RGB, FLOAT, face.as_ptr() as *const GLvoid);
+            }
+            PixelStorei(UNPACK_ALIGNMENT, 4);
+
+            TexParameteri(TEXTURE_CUBE_MAP, TEXTURE_WRAP_S, CLAMP_TO_EDGE as i32);
+            TexParameteri(TEXTURE_CUBE_MAP, TEXTURE_WRAP_T, CLAMP_TO_EDGE as i32);
+            TexParameteri(TEXTURE_CUBE_MAP, TEXTURE_WRAP_R, CLAMP_TO_EDGE as i32);
+            TexParameteri(TEXTURE_CUBE_MAP, TEXTURE_MIN_FILTER, LINEAR_MIPMAP_LINEAR as i32);
+            TexParameteri(TEXTURE_CUBE_MAP, TEXTURE_MAG_FILTER, LINEAR as i32);
+            GenerateMipmap(TEXTURE_CUBE_MAP);
+            BindTexture(TEXTURE_CUBE_MAP, 0);
+        }
+        Cubemap { id, size }
+    }
+}
+
+// the direction through the texel at (u, v) (-1 to 1, v going down the image) of a face,
+// following the face orientations of the GL spec
+fn face_direction(face: u32, u: f32, v: f32) -> Vec3{
+    match face{
+        0 => vec3(1., -v, -u),
+        1 => vec3(-1., -v, u),
+        2 => vec3(u, 1., v),
+        3 => vec3(u, -1., -v),
+        4 => vec3(u, -v, 1.),
+        _ => vec3(-u, -v, -1.),
+    }
+}
+
+// bilinear, wrapping around horizontally
+fn sample_panorama(panorama: &Rgb32FImage, direction: Vec3) -> [f32; 3]{
+    let (width, height) = (panorama.width() as f32, panorama.height() as f32);
+    let u = direction.z.atan2(direction.x) / (2. * PI) + 0.5;
+    // the top row of the image is straight up
+    let v = 0.5 - direction.y.clamp(-1., 1.).asin() / PI;
+
+    let x = u * width - 0.5;
+    let y = (v * height - 0.5).clamp(0., height - 1.);
+    let (x0, y0) = (x.floor(), y.floor());
+    let (tx, ty) = (x - x0, y - y0);
+
+    let texel = |x: f32, y: f32| {
+        let x = x.rem_euclid(width) as u32;
+        let y = (y as u32).min(panorama.height() - 1);
+        Vec3::from(panorama.get_pixel(x, y).0)
+    };
+    let top = texel(x0, y0).lerp(texel(x0 + 1., y0), tx);
+    let bottom = texel(x0, y0 + 1.).lerp(texel(x0 + 1., y0 + 1.), tx);
+    top.lerp(bottom, ty).to_array()
+}
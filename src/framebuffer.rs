@@ -0,0 +1,175 @@
+use std::ptr;
+
+use gl::{*, types::*};
+
+// An offscreen render target: a color attachment plus an optional depth/stencil one.
+// Single sampled framebuffers keep their color in a texture that can be sampled afterwards,
+// multisampled ones use renderbuffers and have to be resolved (blit_to) into a single sampled one first.
+pub struct Framebuffer{
+    pub fbo: u32,
+    // texture, or renderbuffer when multisampled
+    pub color: u32,
+    // renderbuffer, 0 when there is no depth attachment
+    pub depth: u32,
+
+    pub width: i32,
+    pub height: i32,
+    pub samples: i32,
+    pub color_format: GLenum,
+    pub has_depth: bool,
+}
+
+impl Framebuffer{
+    pub fn new(width: i32, height: i32, samples: i32, color_format: GLenum) -> Self{
+        let mut framebuffer = Framebuffer{
+            fbo: 0,
+            color: 0,
+            depth: 0,
+
+            width: width.max(1),
+            height: height.max(1),
+            samples: samples.max(1),
+            color_format,
+            has_depth: true,
+        };
+        framebuffer.create();
+        framebuffer
+    }
+
+    // for intermediate passes that only read and write colors
+    pub fn color_only(width: i32, height: i32, color_format: GLenum) -> Self{
+        let mut framebuffer = Framebuffer{
+            fbo: 0,
+            color: 0,
+            depth: 0,
+
+            width: width.max(1),
+            height: height.max(1),
+            samples: 1,
+            color_format,
+            has_depth: false,
+        };
+        framebuffer.create();
+        framebuffer
+    }
+
+    pub fn multisampled(&self) -> bool{
+        self.samples > 1
+    }
+
+    fn create(&mut self){
+        unsafe{
+            GenFramebuffers(1, &mut self.fbo);
+            BindFramebuffer(FRAMEBUFFER, self.fbo);
+
+            if self.multisampled(){
+                GenRenderbuffers(1, &mut self.color);
+                BindRenderbuffer(RENDERBUFFER, self.color);
+                RenderbufferStorageMultisample(RENDERBUFFER, self.samples, self.color_format, self.width, self.height);
+                FramebufferRenderbuffer(FRAMEBUFFER, COLOR_ATTACHMENT0, RENDERBUFFER, self.color);
+            }
+            else{
+                GenTextures(1, &mut self.color);
+                BindTexture(TEXTURE_2D, self.color);
+                // the type only matters for the (null) initial data
+                TexImage2D(TEXTURE_2D, 0, self.color_format as GLint, self.width, self.height, 0, RGBA, FLOAT, ptr::null());
+                TexParameteri(TEXTURE_2D, TEXTURE_MIN_FILTER, LINEAR as i32);
+                TexParameteri(TEXTURE_2D, TEXTURE_MAG_FILTER, LINEAR as i32);
+                TexParameteri(TEXTURE_2D, TEXTURE_WRAP_S, CLAMP_TO_EDGE as i32);
+                TexParameteri(TEXTURE_2D, TEXTURE_WRAP_T, CLAMP_TO_EDGE as i32);
+                BindTexture(TEXTURE_2D, 0);
+                FramebufferTexture2D(FRAMEBUFFER, COLOR_ATTACHMENT0, TEXTURE_2D, self.color, 0);
+            }
+
+            if self.has_depth{
+                GenRenderbuffers(1, &mut self.depth);
+                BindRenderbuffer(RENDERBUFFER, self.depth);
+                RenderbufferStorageMultisample(RENDERBUFFER, if self.multisampled() {self.samples} else {0}, DEPTH24_STENCIL8, self.width, self.height);
+                FramebufferRenderbuffer(FRAMEBUFFER, DEPTH_STENCIL_ATTACHMENT, RENDERBUFFER, self.depth);
+            }
+            BindRenderbuffer(RENDERBUFFER, 0);
+
+            let status = CheckFramebufferStatus(FRAMEBUFFER);
+            BindFramebuffer(FRAMEBUFFER, 0);
+            if status != FRAMEBUFFER_COMPLETE{
+                panic!("framebuffer {}x{} ({} samples) is incomplete: 0x{:x}", self.width, self.height, self.samples, status);
+            }
+        }
+    }
+
+    fn delete(&mut self){
+        unsafe{
+            if self.fbo != 0{
+                DeleteFramebuffers(1, &self.fbo);
+            }
+            if self.color != 0{
+                if self.multisampled(){
+                    DeleteRenderbuffers(1, &self.color);
+                }
+                else{
+                    DeleteTextures(1, &self.color);
+                }
+            }
+            if self.depth != 0{
+                DeleteRenderbuffers(1, &self.depth);
+            }
+        }
+        self.fbo = 0;
+        self.color = 0;
+        self.depth = 0;
+    }
+
+    // recreates the attachments, their content is lost
+    pub fn resize(&mut self, width: i32, height: i32){
+        let (width, height) = (width.max(1), height.max(1));
+        if width == self.width && height == self.height{
+            return;
+        }
+
+        self.delete();
+        self.width = width;
+        self.height = height;
+        self.create();
+    }
+
+    // draws go here from now on, the viewport is set to cover it
+    pub fn bind(&self){
+        unsafe{
+            BindFramebuffer(FRAMEBUFFER, self.fbo);
+            Viewport(0, 0, self.width, self.height);
+        }
+    }
+
+    // back to the window
+    pub fn bind_default(width: i32, height: i32){
+        unsafe{
+            BindFramebuffer(FRAMEBUFFER, 0);
+            Viewport(0, 0, width, height);
+        }
+    }
+
+    // copies (and resolves, when multisampled) the color into another framebuffer, stretching it if needed
+    pub fn blit_to(&self, target: &Framebuffer){
+        self.blit(target.fbo, target.width, target.height);
+    }
+
+    pub fn blit_to_default(&self, width: i32, height: i32){
+        self.blit(0, width, height);
+    }
+
+    fn blit(&self, target: u32, width: i32, height: i32){
+        let filter = if width == self.width && height == self.height {NEAREST} else {LINEAR};
+        unsafe{
+            BindFramebuffer(READ_FRAMEBUFFER, self.fbo);
+            BindFramebuffer(DRAW_FRAMEBUFFER, target);
+            BlitFramebuffer(0, 0, self.width, self.height, 0, 0, width, height, COLOR_BUFFER_BIT, filter);
+            BindFramebuffer(FRAMEBUFFER, 0);
+        }
+    }
+}
+
+impl Drop for Framebuffer{
+    fn drop(&mut self){
+        self.delete();
+    }
+}
@@ -1,14 +1,15 @@
-use std::collections::HashMap;
+use std::{collections::HashMap, rc::Rc};
 
 use glam::{EulerRot, Mat4, Quat, Vec3, Vec4};
 
-use crate::{line::Line, mesh::Mesh, shapes::{make_shape, Shapes}, texture::make_tex, transform::{self, Transform}, vertex::Vertex};
+use crate::{material::Material, mesh::Mesh, scene::Scene, shapes::{make_shape, Shapes}, texture::Texture, transform::{self, Transform}, vertex::Vertex};
 
 #[derive(Clone, Copy)]
 pub struct GameObject<T>{
     pub object: T,
     pub transform: Transform,
     pub color: Vec4,
+    pub cast_shadows: bool,
 }
 
 impl GameObject<Mesh>{
@@ -19,15 +20,18 @@ impl GameObject<Mesh>{
             object: mesh,
             transform,
             color: Vec4::ONE,
+            cast_shadows: true,
         }
     }
 
-    pub fn draw(&self, view_position: Vec3){
-        self.object.draw(view_position, self.transform);
+    pub fn draw(&self, scene: &Scene){
+        self.object.draw(self.transform, scene);
     }
 
     pub fn set_shape(&mut self, new_shape: Shapes){
+        let material = self.object.material.clone();
         self.object = make_shape(new_shape, self.transform, self.color);
+        self.object.set_material(material);
         self.object.update_mesh();
     }
 
@@ -67,8 +71,12 @@ impl GameObject<Mesh>{
         self.color
     }
 
-    pub fn set_texture(&mut self, texture: u32){
-        self.object.texture = texture;
+    pub fn set_texture(&mut self, texture: Rc<Texture>){
+        self.object.set_texture(texture);
+    }
+
+    pub fn set_material(&mut self, material: Rc<Material>){
+        self.object.set_material(material);
     }
 
     pub fn set_shader(&mut self, vert_path: &str, frag_path: &str){
@@ -79,36 +87,3 @@ impl GameObject<Mesh>{
         self.object.setup_mesh();
     }
 }
-
-impl GameObject<Line>{
-    pub fn new(begin: Vec3, end: Vec3, bidimensional: bool) -> Self{
-        Self{
-            object: Line::new(begin, end, Vec4::ONE, bidimensional),
-            transform: Transform::new(),
-            color: Vec4::ONE,
-        }
-    }
-
-    pub fn set_begin(&mut self, begin: Vec3){
-        self.object.begin = begin;
-        self.object.update();
-    }
-
-    pub fn set_end(&mut self, end: Vec3){
-        self.object.end = end;
-        self.object.update();
-    }
-
-    pub fn set_color(&mut self, color: Vec4){
-        self.object.color = color;
-        self.object.update();
-    }
-
-    pub fn setup_mesh(&mut self){
-        self.object.mesh.setup_mesh();
-    }
-
-    pub fn draw(&self, view_position: Vec3){
-        self.object.draw(view_position, self.transform);
-    }
-}
@@ -0,0 +1,185 @@
+use std::ffi::{c_char, c_int, c_void, CStr, CString};
+
+// A GL 3.3 core context without a window or any display server, for rendering in tests (see
+// regression.rs). GLFW 3.3 can't make one, so it comes straight from EGL: libEGL is loaded at
+// runtime and the display is Mesa's software device (llvmpipe), picked by its
+// EGL_MESA_device_software extension, so the result is the same on every machine whatever GPU it
+// has. There is no default framebuffer, draws have to go to a Framebuffer.
+
+type EGLDisplay = *mut c_void;
+type EGLDeviceEXT = *mut c_void;
+type EGLContext = *mut c_void;
+type EGLBoolean = u32;
+type EGLint = i32;
+type EGLenum = u32;
+
+const EGL_NONE: EGLint = 0x3038;
+const EGL_EXTENSIONS: EGLint = 0x3055;
+const EGL_CONTEXT_MAJOR_VERSION: EGLint = 0x3098;
+const EGL_CONTEXT_MINOR_VERSION: EGLint = 0x30FB;
+const EGL_CONTEXT_OPENGL_PROFILE_MASK: EGLint = 0x30FD;
+const EGL_CONTEXT_OPENGL_CORE_PROFILE_BIT: EGLint = 0x1;
+const EGL_OPENGL_API: EGLenum = 0x30A2;
+const EGL_PLATFORM_DEVICE_EXT: EGLenum = 0x313F;
+
+const RTLD_NOW: c_int = 2;
+
+#[link(name = "dl")]
+extern "C"{
+    fn dlopen(filename: *const c_char, flags: c_int) -> *mut c_void;
+    fn dlsym(handle: *mut c_void, symbol: *const c_char) -> *mut c_void;
+    fn dlclose(handle: *mut c_void) -> c_int;
+}
+
+struct Egl{
+    get_proc_address: unsafe extern "C" fn(*const c_char) -> *mut c_void,
+    initialize: unsafe extern "C" fn(EGLDisplay, *mut EGLint, *mut EGLint) -> EGLBoolean,
+    terminate: unsafe extern "C" fn(EGLDisplay) -> EGLBoolean,
+    query_string: unsafe extern "C" fn(EGLDisplay, EGLint) -> *const c_char,
+    bind_api: unsafe extern "C" fn(EGLenum) -> EGLBoolean,
+    create_context: unsafe extern "C" fn(EGLDisplay, *mut c_void, EGLContext, *const EGLint) -> EGLContext,
+    destroy_context: unsafe extern "C" fn(EGLDisplay, EGLContext) -> EGLBoolean,
+    make_current: unsafe extern "C" fn(EGLDisplay, *mut c_void, *mut c_void, EGLContext) -> EGLBoolean,
+    get_error: unsafe extern "C" fn() -> EGLint,
+}
+
+pub struct HeadlessContext{
+    library: *mut c_void,
+    egl: Egl,
+    display: EGLDisplay,
+    context: EGLContext,
+}
+
+impl HeadlessContext{
+    // makes the context current on this thread and loads the GL functions from it
+    pub fn new() -> Result<Self, String>{
+        unsafe{
+            let library = dlopen(c"libEGL.so.1".as_ptr(), RTLD_NOW);
+            if library.is_null(){
+                return Err("libEGL.so.1 not found".to_string());
+            }
+
+            match Self::create(library){
+                Ok(context) => Ok(context),
+                Err(error) => {
+                    dlclose(library);
+                    Err(error)
+                }
+            }
+        }
+    }
+
+    // the function types of `load!` and `extension_function!` come from what they are assigned to
+    #[allow(clippy::missing_transmute_annotations)]
+    unsafe fn create(library: *mut c_void) -> Result<Self, String>{
+        macro_rules! load{
+            ($name:literal) => {{
+                let symbol = dlsym(library, CString::new($name).unwrap().as_ptr());
+                if symbol.is_null(){
+                    return Err(format!("libEGL has no {}", $name));
+                }
+                std::mem::transmute::<*mut c_void, _>(symbol)
+            }};
+        }
+
+        let egl = Egl{
+            get_proc_address: load!("eglGetProcAddress"),
+            initialize: load!("eglInitialize"),
+            terminate: load!("eglTerminate"),
+            query_string: load!("eglQueryString"),
+            bind_api: load!("eglBindAPI"),
+            create_context: load!("eglCreateContext"),
+            destroy_context: load!("eglDestroyContext"),
+            make_current: load!("eglMakeCurrent"),
+            get_error: load!("eglGetError"),
+        };
+
+        // the client extensions, queried without a display
+        let client_extensions = (egl.query_string)(std::ptr::null_mut(), EGL_EXTENSIONS);
+        for extension in ["EGL_EXT_device_enumeration", "EGL_EXT_platform_device"]{
+            if client_extensions.is_null() || !has_extension(client_extensions, extension){
+                return Err(format!("EGL has no {}", extension));
+            }
+        }
+        macro_rules! extension_function{
+            ($name:literal) => {{
+                let function = (egl.get_proc_address)(CString::new($name).unwrap().as_ptr());
+                if function.is_null(){
+                    return Err(format!("EGL has no {}", $name));
+                }
+                std::mem::transmute::<*mut c_void, _>(function)
+            }};
+        }
+        let query_devices: unsafe extern "C" fn(EGLint, *mut EGLDeviceEXT, *mut EGLint) -> EGLBoolean = extension_function!("eglQueryDevicesEXT");
+        let query_device_string: unsafe extern "C" fn(EGLDeviceEXT, EGLint) -> *const c_char = extension_function!("eglQueryDeviceStringEXT");
+        let get_platform_display: unsafe extern "C" fn(EGLenum, *mut c_void, *const EGLint) -> EGLDisplay = extension_function!("eglGetPlatformDisplayEXT");
+
+        let mut devices = [std::ptr::null_mut(); 16];
+        let mut device_count = 0;
+        query_devices(devices.len() as EGLint, devices.as_mut_ptr(), &mut device_count);
+        let software_device = devices[..device_count.max(0) as usize].iter().find(|&&device| {
+            let extensions = query_device_string(device, EGL_EXTENSIONS);
+            !extensions.is_null() && has_extension(extensions, "EGL_MESA_device_software")
+        });
+        let Some(&software_device) = software_device else{
+            return Err("EGL has no software device (EGL_MESA_device_software)".to_string());
+        };
+
+        let display = get_platform_display(EGL_PLATFORM_DEVICE_EXT, software_device, std::ptr::null());
+        if display.is_null() || (egl.initialize)(display, std::ptr::null_mut(), std::ptr::null_mut()) == 0{
+            return Err(format!("failed to initialize the software EGL display (error {:#x})", (egl.get_error)()));
+        }
+
+        let display_extensions = (egl.query_string)(display, EGL_EXTENSIONS);
+        for extension in ["EGL_KHR_no_config_context", "EGL_KHR_surfaceless_context"]{
+            if display_extensions.is_null() || !has_extension(display_extensions, extension){
+                (egl.terminate)(display);
+                return Err(format!("EGL display has no {}", extension));
+            }
+        }
+
+        let attributes = [
+            EGL_CONTEXT_MAJOR_VERSION, 3,
+            EGL_CONTEXT_MINOR_VERSION, 3,
+            EGL_CONTEXT_OPENGL_PROFILE_MASK, EGL_CONTEXT_OPENGL_CORE_PROFILE_BIT,
+            EGL_NONE,
+        ];
+        (egl.bind_api)(EGL_OPENGL_API);
+        // no config (EGL_NO_CONFIG_KHR) and no surface, there is nothing to draw to but framebuffer objects
+        let context = (egl.create_context)(display, std::ptr::null_mut(), std::ptr::null_mut(), attributes.as_ptr());
+        if context.is_null(){
+            let error = (egl.get_error)();
+            (egl.terminate)(display);
+            return Err(format!("failed to create a GL 3.3 core context (error {:#x})", error));
+        }
+        if (egl.make_current)(display, std::ptr::null_mut(), std::ptr::null_mut(), context) == 0{
+            let error = (egl.get_error)();
+            (egl.destroy_context)(display, context);
+            (egl.terminate)(display);
+            return Err(format!("failed to make the context current (error {:#x})", error));
+        }
+
+        gl::load_with(|symbol| {
+            let symbol = CString::new(symbol).unwrap();
+            (egl.get_proc_address)(symbol.as_ptr()) as *const _
+        });
+
+        Ok(HeadlessContext { library, egl, display, context })
+    }
+}
+
+fn has_extension(extensions: *const c_char, name: &str) -> bool{
+    let extensions = unsafe {CStr::from_ptr(extensions)};
+    extensions.to_string_lossy().split_whitespace().any(|extension| extension == name)
+}
+
+impl Drop for HeadlessContext{
+    fn drop(&mut self){
+        unsafe{
+            (self.egl.make_current)(self.display, std::ptr::null_mut(), std::ptr::null_mut(), std::ptr::null_mut());
+            (self.egl.destroy_context)(self.display, self.context);
+            (self.egl.terminate)(self.display);
+            dlclose(self.library);
+        }
+    }
+}
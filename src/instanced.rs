@@ -0,0 +1,178 @@
+use std::{ptr, rc::Rc};
+
+use gl::{*, types::*};
+use glam::{Mat4, Vec4};
+
+use crate::{material::Material, mesh::{get_model_matrix, Mesh}, resources, scene::Scene, shader::Shader, shapes::Shapes, texture::Texture, texture_array::TextureArray, transform::Transform, vertex::Vertex};
+
+pub struct Instance{
+    pub model: Mat4,
+    pub color: Vec4,
+    // of the material's albedo_array, a float since that's what the attribute is
+    pub layer: f32,
+}
+
+// One mesh drawn many times with a single draw call, each copy having its own model matrix, color
+// and texture array layer.
+// The mesh buffers are shared (it must already be set up), only the vao and the instance buffer are ours.
+pub struct InstancedMesh{
+    pub mesh: Rc<Mesh>,
+    pub shader: Rc<Shader>,
+    pub instances: Vec<Instance>,
+
+    pub material: Rc<Material>,
+    pub cast_shadows: bool,
+
+    pub vao: u32,
+    pub instance_vbo: u32,
+    uploaded_instances: usize,
+}
+
+impl InstancedMesh{
+    pub fn new(mesh: Rc<Mesh>) -> Self{
+        InstancedMesh{
+            mesh,
+            shader: resources::shader("src/shaders/instanced_lit_shader.vs", "src/shaders/default_lit_shader.fs"),
+            instances: vec![],
+
+            material: Rc::new(Material::new()),
+            cast_shadows: true,
+
+            vao: 0,
+            instance_vbo: 0,
+            uploaded_instances: 0,
+        }
+    }
+
+    pub fn from_shape(shape: Shapes) -> Self{
+        InstancedMesh::new(resources::shape(shape))
+    }
+
+    pub fn set_material(&mut self, material: Rc<Material>){
+        self.shader = material.shader_for(&self.shader);
+        self.material = material;
+    }
+
+    pub fn set_texture(&mut self, texture: Rc<Texture>){
+        Rc::make_mut(&mut self.material).albedo_texture = Some(texture);
+        self.shader = self.material.shader_for(&self.shader);
+    }
+
+    pub fn setup_mesh(&mut self){
+        unsafe{
+            GenVertexArrays(1, &mut self.vao);
+            BindVertexArray(self.vao);
+
+            BindBuffer(ARRAY_BUFFER, self.mesh.vbo);
+            let vertex_stride = std::mem::size_of::<Vertex>() as GLsizei;
+            let vertex_attributes = [
+                (0, 3, std::mem::offset_of!(Vertex, position)),
+                (1, 4, std::mem::offset_of!(Vertex, color)),
+                (2, 2, std::mem::offset_of!(Vertex, tex_coords)),
+                (3, 3, std::mem::offset_of!(Vertex, normal)),
+            ];
+            for (index, dimension, offset) in vertex_attributes{
+                EnableVertexAttribArray(index);
+                VertexAttribPointer(index, dimension, FLOAT, FALSE, vertex_stride, offset as *const std::ffi::c_void);
+            }
+            BindBuffer(ELEMENT_ARRAY_BUFFER, self.mesh.ebo);
+
+            GenBuffers(1, &mut self.instance_vbo);
+            BindBuffer(ARRAY_BUFFER, self.instance_vbo);
+
+            let stride = std::mem::size_of::<Instance>() as GLsizei;
+
+            // a mat4 attribute is sent as 4 vec4 columns
+            let model_offset = std::mem::offset_of!(Instance, model);
+            for column in 0..4{
+                let index = 4 + column as u32;
+                let offset = (model_offset + column * std::mem::size_of::<Vec4>()) as *const std::ffi::c_void;
+                EnableVertexAttribArray(index);
+                VertexAttribPointer(index, 4, FLOAT, FALSE, stride, offset);
+                VertexAttribDivisor(index, 1);
+            }
+
+            let color_offset = std::mem::offset_of!(Instance, color) as *const std::ffi::c_void;
+            EnableVertexAttribArray(8);
+            VertexAttribPointer(8, 4, FLOAT, FALSE, stride, color_offset);
+            VertexAttribDivisor(8, 1);
+
+            let layer_offset = std::mem::offset_of!(Instance, layer) as *const std::ffi::c_void;
+            EnableVertexAttribArray(9);
+            VertexAttribPointer(9, 1, FLOAT, FALSE, stride, layer_offset);
+            VertexAttribDivisor(9, 1);
+
+            BindVertexArray(0);
+        }
+    }
+
+    pub fn clear(&mut self){
+        self.instances.clear();
+    }
+
+    pub fn push(&mut self, transform: Transform, color: Vec4){
+        self.push_layer(transform, color, 0);
+    }
+
+    // `layer` picks the image of the material's albedo_array (see TextureArrayBuilder::add)
+    pub fn push_layer(&mut self, transform: Transform, color: Vec4, layer: u32){
+        self.instances.push(Instance { model: get_model_matrix(transform), color, layer: layer as f32 });
+    }
+
+    pub fn set_texture_array(&mut self, texture_array: Rc<TextureArray>){
+        Rc::make_mut(&mut self.material).albedo_array = Some(texture_array);
+        self.shader = self.material.shader_for(&self.shader);
+    }
+
+    // sends the pushed instances to the GPU, what gets drawn until the next upload
+    pub fn upload_instances(&mut self){
+        unsafe {
+            BindBuffer(ARRAY_BUFFER, self.instance_vbo);
+            BufferData(
+                ARRAY_BUFFER,
+                (self.instances.len() * std::mem::size_of::<Instance>()) as isize,
+                self.instances.as_ptr() as *const _,
+                DYNAMIC_DRAW,
+            );
+        }
+        self.uploaded_instances = self.instances.len();
+    }
+
+    pub fn instance_count(&self) -> usize{
+        self.uploaded_instances
+    }
+
+    // the camera comes from the uniform blocks, see Scene::upload_frame_uniforms
+    pub fn draw(&self, scene: &Scene){
+        if self.uploaded_instances == 0{
+            return;
+        }
+
+        unsafe {
+            let shader = &self.shader;
+            shader.useProgram();
+            BindVertexArray(self.vao);
+
+            scene.set_uniforms(shader);
+
+            self.material.bind(shader);
+
+            DrawElementsInstanced(gl::TRIANGLES, self.mesh.indices.len() as i32, gl::UNSIGNED_INT, ptr::null(), self.uploaded_instances as i32);
+            BindVertexArray(0);
+            UseProgram(0);
+        };
+    }
+}
+
+impl Drop for InstancedMesh{
+    fn drop(&mut self){
+        unsafe{
+            if self.vao != 0{
+                DeleteVertexArrays(1, &self.vao);
+            }
+            if self.instance_vbo != 0{
+                DeleteBuffers(1, &self.instance_vbo);
+            }
+        }
+    }
+}
@@ -1,8 +1,116 @@
-use glam::Vec3;
+use glam::{vec3, Vec3};
 
-pub static mut LIGHTS: Vec<Light> = vec![];
+use crate::{scene::Scene, shadow::MAX_SHADOW_LAYERS, uniform_buffer::{LightData, LightsBlock}};
 
+// given to every shader as a #define (see preprocessor::engine_defines)
+pub const MAX_LIGHTS: usize = 32;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum LightType{
+    // infinitely far away (sun like), only the direction matters
+    Directional,
+    Point,
+    Spot,
+}
+
+#[derive(Clone, Copy)]
 pub struct Light{
+    pub kind: LightType,
     pub position: Vec3,
+    pub direction: Vec3,
     pub color: Vec3,
+    pub intensity: f32,
+
+    // constant, linear and quadratic terms: 1 / (c + l*d + q*d^2)
+    pub attenuation: Vec3,
+
+    // spot cone, in degrees: full intensity inside the inner angle, fading to zero at the outer one
+    pub inner_angle: f32,
+    pub outer_angle: f32,
+
+    // only directional and spot lights can, see shadow::ShadowMaps
+    pub cast_shadows: bool,
+}
+
+impl Light{
+    pub fn directional(direction: Vec3, color: Vec3) -> Self{
+        Light{
+            kind: LightType::Directional,
+            position: Vec3::ZERO,
+            direction: direction.normalize(),
+            color,
+            intensity: 1.,
+            attenuation: vec3(1., 0., 0.),
+            inner_angle: 0.,
+            outer_angle: 0.,
+            cast_shadows: false,
+        }
+    }
+
+    pub fn point(position: Vec3, color: Vec3) -> Self{
+        Light{
+            kind: LightType::Point,
+            position,
+            direction: Vec3::NEG_Z,
+            color,
+            intensity: 1.,
+            attenuation: vec3(1., 0.045, 0.0075),
+            inner_angle: 0.,
+            outer_angle: 0.,
+            cast_shadows: false,
+        }
+    }
+
+    pub fn spot(position: Vec3, direction: Vec3, color: Vec3, inner_angle: f32, outer_angle: f32) -> Self{
+        Light{
+            kind: LightType::Spot,
+            position,
+            direction: direction.normalize(),
+            color,
+            intensity: 1.,
+            attenuation: vec3(1., 0.045, 0.0075),
+            inner_angle,
+            outer_angle,
+            cast_shadows: false,
+        }
+    }
+}
+
+// The lights (and their shadow maps) of the scene as the `Lights` block of the lit shaders
+// wants them. Lights past MAX_LIGHTS are ignored.
+pub fn lights_block(scene: &Scene) -> LightsBlock{
+    let mut block = LightsBlock::new();
+    let count = scene.lights.len().min(MAX_LIGHTS);
+    block.light_count = count as i32;
+
+    for (i, light) in scene.lights.iter().take(count).enumerate(){
+        let kind = match light.kind{
+            LightType::Directional => 0,
+            LightType::Point => 1,
+            LightType::Spot => 2,
+        };
+        let shadow_layer = scene.shadow_maps.as_ref().and_then(|shadow_maps| shadow_maps.light_layers.get(i).copied()).unwrap_or(-1);
+
+        block.lights[i] = LightData{
+            position: light.position,
+            kind,
+            direction: light.direction.normalize(),
+            inner_cutoff: light.inner_angle.to_radians().cos(),
+            color: light.color * light.intensity,
+            outer_cutoff: light.outer_angle.to_radians().cos(),
+            attenuation: light.attenuation,
+            shadow_layer,
+        };
+    }
+
+    if let Some(shadow_maps) = &scene.shadow_maps{
+        for (i, matrix) in shadow_maps.matrices.iter().take(MAX_SHADOW_LAYERS).enumerate(){
+            block.shadow_matrices[i] = *matrix;
+        }
+        for (i, split) in shadow_maps.cascade_splits.iter().enumerate(){
+            block.cascade_splits[i][0] = *split;
+        }
+    }
+
+    block
 }
@@ -0,0 +1,107 @@
+use std::rc::Rc;
+
+use gl::{*, types::*};
+use glam::{Mat4, Vec2, Vec3, Vec4};
+
+use crate::{resources, shader::Shader, vertex::Vertex};
+
+// Collects line segments during a frame and draws all of them with one GL_LINES call.
+// Much cheaper than a mesh per segment for debug drawing (constraints, normals...).
+// Lines are always 1 pixel wide, core contexts don't support wider ones
+pub struct LineBatch{
+    pub vertices: Vec<Vertex>,
+    pub shader: Rc<Shader>,
+
+    pub vao: u32,
+    pub vbo: u32,
+    capacity: usize,
+}
+
+impl LineBatch{
+    pub fn new() -> Self{
+        LineBatch{
+            vertices: vec![],
+            shader: resources::shader("src/shaders/basic_shader.vs", "src/shaders/basic_shader.fs"),
+
+            vao: 0,
+            vbo: 0,
+            capacity: 0,
+        }
+    }
+
+    pub fn setup_mesh(&mut self){
+        unsafe{
+            GenVertexArrays(1, &mut self.vao);
+            GenBuffers(1, &mut self.vbo);
+
+            BindVertexArray(self.vao);
+            BindBuffer(ARRAY_BUFFER, self.vbo);
+            let stride = std::mem::size_of::<Vertex>() as GLsizei;
+            EnableVertexAttribArray(0);
+            VertexAttribPointer(0, 3, FLOAT, FALSE, stride, std::mem::offset_of!(Vertex, position) as *const std::ffi::c_void);
+            EnableVertexAttribArray(1);
+            VertexAttribPointer(1, 4, FLOAT, FALSE, stride, std::mem::offset_of!(Vertex, color) as *const std::ffi::c_void);
+            BindVertexArray(0);
+        }
+    }
+
+    pub fn clear(&mut self){
+        self.vertices.clear();
+    }
+
+    pub fn push(&mut self, begin: Vec3, end: Vec3, color: Vec4){
+        self.vertices.push(Vertex { position: begin, color, tex_coords: Vec2::ZERO, normal: Vec3::ZERO });
+        self.vertices.push(Vertex { position: end, color, tex_coords: Vec2::ZERO, normal: Vec3::ZERO });
+    }
+
+    // sends the segments pushed this frame to the GPU, before drawing (see RenderQueue::push_lines)
+    pub fn upload(&mut self){
+        if self.vertices.is_empty(){
+            return;
+        }
+
+        unsafe{
+            BindBuffer(ARRAY_BUFFER, self.vbo);
+
+            let size = (self.vertices.len() * std::mem::size_of::<Vertex>()) as isize;
+            // only grow when the segments don't fit anymore, otherwise orphan the storage and refill it
+            if self.vertices.len() > self.capacity{
+                self.capacity = self.vertices.len().next_power_of_two();
+            }
+            BufferData(ARRAY_BUFFER, (self.capacity * std::mem::size_of::<Vertex>()) as isize, std::ptr::null(), STREAM_DRAW);
+            BufferSubData(ARRAY_BUFFER, 0, size, self.vertices.as_ptr() as *const _);
+            BindBuffer(ARRAY_BUFFER, 0);
+        }
+    }
+
+    pub fn draw(&self){
+        if self.vertices.is_empty(){
+            return;
+        }
+
+        unsafe{
+            BindVertexArray(self.vao);
+            self.shader.useProgram();
+
+            self.shader.set_uniform("model", Mat4::IDENTITY);
+
+            DrawArrays(LINES, 0, self.vertices.len() as GLsizei);
+
+            BindVertexArray(0);
+            UseProgram(0);
+        }
+    }
+}
+
+impl Drop for LineBatch{
+    fn drop(&mut self){
+        unsafe{
+            if self.vao != 0{
+                DeleteVertexArrays(1, &self.vao);
+            }
+            if self.vbo != 0{
+                DeleteBuffers(1, &self.vbo);
+            }
+        }
+    }
+}
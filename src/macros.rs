@@ -1,12 +1,10 @@
-#![allow(deref_nullptr)]
-
 use gl::{*, types::*};
 
 #[macro_export]
 macro_rules! gen_attrib_pointers {
     ($struct_name:ident, $($index:expr => $field_name:ident: $dimension:expr),*) => {
         $(
-            let offset = &((*std::ptr::null::<$struct_name>()).$field_name) as *const _ as *const std::ffi::c_void;
+            let offset = std::mem::offset_of!($struct_name, $field_name) as *const std::ffi::c_void;
             EnableVertexAttribArray($index);
             VertexAttribPointer($index, $dimension, FLOAT, FALSE, std::mem::size_of::<$struct_name>() as GLsizei, offset);
         )*
@@ -15,10 +13,13 @@ macro_rules! gen_attrib_pointers {
 
 #[macro_export]
 macro_rules! bind_buffer {
-    ($buffer_type:expr, $buffer:expr, $data:expr) => {{
+    ($buffer_type:expr, $buffer:expr, $data:expr) => {
+        bind_buffer!($buffer_type, $buffer, $data, STATIC_DRAW)
+    };
+    ($buffer_type:expr, $buffer:expr, $data:expr, $usage:expr) => {{
         BindBuffer($buffer_type, $buffer);
         let size = ($data.len() * std::mem::size_of_val(&$data[0])) as isize;
         let data_ptr = &$data[0] as *const _ as *const std::ffi::c_void;
-        BufferData($buffer_type, size, data_ptr, STATIC_DRAW);
+        BufferData($buffer_type, size, data_ptr, $usage);
     }};
 }
@@ -9,126 +9,259 @@ mod shapes;
 mod camera;
 mod texture;
 mod light;
-mod line;
+mod instanced;
+mod resources;
+mod line_batch;
+mod strain_view;
+mod scene;
+mod shadow;
+mod material;
+mod preprocessor;
+mod uniform_buffer;
+mod render_queue;
+mod bounds;
+mod framebuffer;
+mod postprocess;
+mod capture;
+#[cfg(test)]
+mod headless;
+#[cfg(test)]
+mod regression;
+mod cubemap;
+mod skybox;
+mod texture_array;
 
-use std::collections::HashMap;
+use std::{path::Path, rc::Rc};
 
-use game_object::GameObject;
+use instanced::InstancedMesh;
+use capture::FrameRecorder;
+use cubemap::Cubemap;
 use glam::{vec2, vec3, vec4, Quat, Vec2, Vec3, Vec4};
 
 use glfw::{Action, Key};
 use image::imageops::colorops;
-use light::{Light, LIGHTS};
-use line::Line;
-use mesh::Mesh;
-use texture::make_tex;
+use light::Light;
+use line_batch::LineBatch;
+use postprocess::PostEffect;
+use render_queue::{RenderQueue, RenderStats};
+use scene::Scene;
+use skybox::Skybox;
+use strain_view::StrainView;
+use texture::TextureDescriptor;
+use texture_array::TextureArrayBuilder;
+use transform::Transform;
 use window::Window;
 
 // settings
 const W: u32 = 800;
 const H: u32 = 600;
+const CAPTION: &str = "ulala babe babe cmon";
 
 pub fn main() {
+    // the window owns the GL context, so it has to be created first: locals are dropped in
+    // reverse order, which lets every mesh, shader and texture free itself while the context is alive
     let mut window = Window::new(W, H);
-    window.set_caption("ulala babe babe cmon");
+    window.set_caption(CAPTION);
+    // the textures and colors are authored for display already, so no ToneMapping/Gamma
+    window.post_process.effects = vec![PostEffect::bloom(), PostEffect::Fxaa, PostEffect::vignette()];
 
-    let mut texture_pack: HashMap<i32, u32> = HashMap::default();
-    texture_pack.insert(0, make_tex("src/textures/container.jpg"));
-    texture_pack.insert(1, make_tex("src/textures/default_tex.png"));
+    // the particles take turns between these, all from the one texture array
+    let mut particle_textures = TextureArrayBuilder::new(256, 256, TextureDescriptor::default());
+    for path in ["src/textures/default_tex.png", "src/textures/container.jpg"]{
+        particle_textures.add_or_default(path);
+    }
+    let particle_textures = Rc::new(particle_textures.build());
+
+    let mut scene = Scene::new();
+    let camera_light = scene.add_light(Light::point(vec3(0., 0., 0.), vec3(1., 1., 1.)));
+
+    let mut sun = Light::directional(vec3(-0.3, -1., -0.5), vec3(1., 1., 1.));
+    sun.intensity = 0.4;
+    sun.cast_shadows = true;
+    scene.add_light(sun);
+    scene.enable_shadows(2048);
 
-    unsafe{
-        LIGHTS.push(Light { position: vec3(0., 0., 0.), color: vec3(1., 1., 1.) });
+    // the sky behind everything, which Pbr materials reflect too
+    match Cubemap::from_equirectangular("src/textures/sky.png", 256){
+        Ok(sky) => {
+            let sky = Rc::new(sky);
+            scene.skybox = Some(Skybox::new(sky.clone()));
+            scene.environment = Some(sky);
+        }
+        Err(error) => println!("Failed to load the sky: {}", error),
     }
 
     let grid_size: i32 = 5;
     let spacing: i32 = 2;
 
-    let mut particles: Vec<GameObject<Mesh>> = vec![];
+    let mut particles: Vec<Particle> = vec![];
+
+    // every particle is drawn with the same sphere, so they all share one instanced mesh
+    let mut particle_renderer = InstancedMesh::from_shape(shapes::Shapes::Sphere);
+    particle_renderer.set_texture_array(particle_textures.clone());
+    particle_renderer.setup_mesh();
 
     for x in 0..grid_size{
         for y in 0..grid_size{
-            let mut m = GameObject::<Mesh>::new(Mesh::empty());
-            m.set_shape(shapes::Shapes::Sphere);
-            m.set_texture(texture_pack[&1]);
-            m.setup_mesh();
+            let mut transform = Transform::new();
+            transform.position = vec3((-grid_size + x*spacing) as f32, (-grid_size + y*spacing) as f32, -3.);
+            transform.scale *= 0.4;
 
-            m.set_position(vec3((-grid_size + x*spacing) as f32, (-grid_size + y*spacing) as f32, -3.));
-            m.scale(0.4);
-            m.set_color(vec4(1., 0., 0., 1.));
-
-            particles.push(m);
+            particles.push(Particle { transform, color: vec4(1., 0., 0., 1.) });
         }
     }
 
-    let mut lines: Vec<GameObject<Line>> = vec![];
+    // all the links are drawn together, refilled every frame from the particle positions
+    let mut lines = LineBatch::new();
+    lines.setup_mesh();
     let mut lines_indexes: Vec<Vec2> = vec![];
+    let mut rest_lengths: Vec<f32> = vec![];
 
-    for (index, mesh) in particles.iter().enumerate(){
+    for index in 0..particles.len(){
         if index < particles.len() - 1 && (index + grid_size as usize) % grid_size as usize != (grid_size - 1) as usize{
-            let mut line1 = GameObject::<Line>::new(mesh.transform.position, particles[index+1].transform.position, false);
-            line1.setup_mesh();
-
-            lines.push(line1);
             lines_indexes.push(vec2(index as f32, index as f32+1.));
+            rest_lengths.push(particles[index].transform.position.distance(particles[index+1].transform.position));
         }
 
         if index as i32 + grid_size < particles.len() as i32{
-            let mut line2 = GameObject::<Line>::new(mesh.transform.position, particles[index+grid_size as usize].transform.position, false);
-            line2.setup_mesh();
-
-            lines.push(line2);
             lines_indexes.push(vec2(index as f32, index as f32+grid_size as f32));
+            rest_lengths.push(particles[index].transform.position.distance(particles[index+grid_size as usize].transform.position));
         }
     }
 
+    // V toggles coloring links by strain and particles by speed
+    let mut strain_view = StrainView::new();
+    let mut last_positions: Vec<Vec3> = particles.iter().map(|particle| particle.transform.position).collect();
+
     let mut new_click = false;
-    let mut closest_particle: usize = 0;
-    let mouse_follow_speed = 8.;
+    let mut grabbed_particle: Option<usize> = None;
+    let mut grab_offset = Vec3::ZERO;
+
+    let mut last_stats = RenderStats::default();
+
+    // F12 saves a screenshot, F9 starts/stops recording every other frame at 60 simulated fps
+    let mut recorder = FrameRecorder::new("recordings", 2, 1. / 60.);
 
     while !window.should_close() {
-        let view_position = window.camera.position;
         window.clear_screen();
 
         window.camera.movement(window.keyboard.clone(), window.dt);
 
-        unsafe{
-            LIGHTS[0].position = window.camera.position;
+        if cfg!(debug_assertions){
+            resources::watch_shaders();
         }
 
+        scene.lights[camera_light].position = window.camera.position;
+
         if window.keyboard[&Key::LeftAlt] == Action::Press{
             window.lock_cursor();
         }
 
+        if window.key_pressed(Key::V){
+            strain_view.toggle();
+        }
+
+        if window.key_pressed(Key::F9){
+            recorder.toggle();
+            window.fixed_timestep = recorder.timestep();
+            println!("{} recording to {}", if recorder.is_recording() {"Started"} else {"Stopped"}, recorder.directory.display());
+        }
+
         if window.mouse_buttons[0] == true{
+            let cursor_area = window.cursor_area();
             if new_click{
-                closest_particle = get_closest_particle_to_mouse(&particles, window.mouse_pos);
+                let mouse_ray = window.camera.screen_ray(window.mouse_pos, cursor_area.x, cursor_area.y);
+                grabbed_particle = get_closest_particle_to_mouse(&particles, window.camera.position, mouse_ray);
+                if let Some(index) = grabbed_particle{
+                    let grabbed_position = particles[index].transform.position;
+                    grab_offset = grabbed_position - window.camera.drag_plane_point(window.mouse_pos, cursor_area.x, cursor_area.y, grabbed_position);
+                }
                 new_click = false;
             }
-            particles[closest_particle].translate(vec3((window.mouse_pos.x - window.last_mouse_pos.x)*mouse_follow_speed/W as f32, -(window.mouse_pos.y - window.last_mouse_pos.y)*mouse_follow_speed/H as f32, 0.));
+            if let Some(index) = grabbed_particle{
+                let grabbed_position = particles[index].transform.position;
+                let cursor_point = window.camera.drag_plane_point(window.mouse_pos, cursor_area.x, cursor_area.y, grabbed_position);
+                particles[index].transform.position = cursor_point + grab_offset;
+            }
         }
         else{
             new_click = true;
         }
 
-        for obj in particles.iter(){
-            obj.draw(view_position);
+        particle_renderer.clear();
+        for (index, particle) in particles.iter().enumerate(){
+            let mut color = particle.color;
+            if strain_view.enabled && window.dt > 0.{
+                let speed = particle.transform.position.distance(last_positions[index]) / window.dt;
+                color = strain_view.particle_color(speed);
+            }
+            particle_renderer.push_layer(particle.transform, color, index as u32 % particle_textures.layers);
+            last_positions[index] = particle.transform.position;
+        }
+        particle_renderer.upload_instances();
+
+        scene.render_shadows(&window.camera, window.w as f32, window.h as f32, |pass| {
+            pass.draw_instanced(&particle_renderer);
+        });
+
+        scene.upload_frame_uniforms(&window.camera, window.time);
+
+        lines.clear();
+        for (index, link) in lines_indexes.iter().enumerate(){
+            let begin = particles[link.x as usize].transform.position;
+            let end = particles[link.y as usize].transform.position;
+
+            let mut color = Vec4::ONE;
+            if strain_view.enabled{
+                color = strain_view.constraint_color(begin.distance(end), rest_lengths[index]);
+            }
+            lines.push(begin, end, color);
+        }
+        lines.upload();
+
+        window.begin_scene();
+
+        let mut render_queue = RenderQueue::new();
+        render_queue.push_instanced(&particle_renderer);
+        render_queue.push_lines(&lines);
+        let stats = render_queue.draw(&scene, window.camera.position);
+        if cfg!(debug_assertions) && stats != last_stats{
+            window.set_caption(&format!("{} - drawn {}, culled {}", CAPTION, stats.drawn, stats.culled));
+            last_stats = stats;
+        }
+
+        window.end_scene();
+
+        // after everything is drawn, before the buffers are swapped
+        let (width, height) = window.framebuffer_size();
+        if window.key_pressed(Key::F12){
+            match capture::save_screenshot(Path::new("screenshots"), width, height){
+                Ok(path) => println!("Saved screenshot {}", path.display()),
+                Err(error) => println!("Failed to save screenshot: {}", error),
+            }
         }
-        for (index, line) in lines.iter_mut().enumerate(){
-            line.set_begin(particles[lines_indexes[index].x as usize].transform.position);
-            line.set_end(particles[lines_indexes[index].y as usize].transform.position);
-            line.draw(view_position);
+        if let Err(error) = recorder.capture(width, height){
+            println!("Failed to save frame, stopping the recording: {}", error);
+            recorder.stop();
+            window.fixed_timestep = recorder.timestep();
         }
 
         window.update();
     }
 }
 
-pub fn get_closest_particle_to_mouse(particles: &Vec<GameObject<Mesh>>, mouse_pos: Vec2) -> usize{
-    let mut answer = 0;
-    for (index, particle) in particles.iter().enumerate(){
-        if particle.transform.position.distance(vec3(mouse_pos.x, mouse_pos.y, 0.)) < particles[answer].transform.position.distance(vec3(mouse_pos.x, mouse_pos.y, 0.)){
-            answer = index;
-        }
-    }
-    answer
+// only drawn through the instanced particle_renderer, so no mesh of their own
+pub struct Particle{
+    pub transform: Transform,
+    pub color: Vec4,
+}
+
+// the particle closest to the ray, None when they are all behind the camera
+pub fn get_closest_particle_to_mouse(particles: &[Particle], ray_origin: Vec3, ray_direction: Vec3) -> Option<usize>{
+    let distance_to_ray = |position: Vec3| (position - ray_origin).cross(ray_direction).length();
+
+    particles.iter().enumerate()
+        .filter(|(_, particle)| (particle.transform.position - ray_origin).dot(ray_direction) > 0.)
+        .min_by(|(_, a), (_, b)| distance_to_ray(a.transform.position).total_cmp(&distance_to_ray(b.transform.position)))
+        .map(|(index, _)| index)
 }
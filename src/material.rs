@@ -0,0 +1,271 @@
+use std::{cell::Cell, rc::Rc};
+
+use gl::*;
+use glam::{Vec3, Vec4};
+
+use crate::{preprocessor, resources, shader::Shader, texture::Texture, texture_array::TextureArray};
+
+// texture units used by the lit shaders, unit 1 is taken by the shadow maps
+pub const ALBEDO_TEXTURE_UNIT: u32 = 0;
+pub const NORMAL_MAP_UNIT: u32 = 2;
+pub const METALLIC_ROUGHNESS_MAP_UNIT: u32 = 3;
+pub const AO_MAP_UNIT: u32 = 4;
+pub const ENVIRONMENT_MAP_UNIT: u32 = 5;
+pub const ALBEDO_ARRAY_UNIT: u32 = 6;
+pub const SPECULAR_MAP_UNIT: u32 = 7;
+pub const EMISSIVE_MAP_UNIT: u32 = 8;
+// custom_textures get the units from here on
+pub const FIRST_CUSTOM_TEXTURE_UNIT: u32 = 9;
+
+thread_local! {
+    // GL_MAX_TEXTURE_IMAGE_UNITS, looked up the first time a custom texture is set
+    static MAX_TEXTURE_UNITS: Cell<Option<u32>> = const { Cell::new(None) };
+}
+
+pub const LIT_FRAGMENT_SHADER: &str = "src/shaders/default_lit_shader.fs";
+pub const PBR_FRAGMENT_SHADER: &str = "src/shaders/pbr_shader.fs";
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Shading{
+    // default_lit_shader.fs, uses specular_strength and shininess
+    Phong,
+    // pbr_shader.fs, uses metallic, roughness, ao and their maps, plus the scene environment
+    Pbr,
+}
+
+// How a surface reacts to light. Meshes share them through an Rc, changing the material of
+// a single mesh (set_texture, set_material...) copies it first so the others aren't affected.
+#[derive(Clone)]
+pub struct Material{
+    pub shading: Shading,
+
+    // multiplied with the vertex colors and the albedo texture, alpha is the opacity
+    pub albedo: Vec4,
+    pub albedo_texture: Option<Rc<Texture>>,
+    // also multiplied in, the layer comes from the instance for instanced meshes (see
+    // InstancedMesh::push_layer) and from albedo_layer for the others
+    pub albedo_array: Option<Rc<TextureArray>>,
+    pub albedo_layer: u32,
+    pub specular_strength: f32,
+    pub shininess: f32,
+    // Phong only, its red channel scales specular_strength
+    pub specular_map: Option<Rc<Texture>>,
+    // light given off by the surface itself, added on top of the lighting
+    pub emissive: Vec3,
+    // multiplied with emissive, which has to be set for it to show
+    pub emissive_map: Option<Rc<Texture>>,
+    // tangent space normals, the tangents are worked out in the fragment shader
+    pub normal_map: Option<Rc<Texture>>,
+    // lit and drawn from both sides, otherwise back faces are culled
+    pub double_sided: bool,
+    // blended with what is behind it using the albedo/texture alpha
+    pub transparent: bool,
+
+    // Pbr only
+    pub metallic: f32,
+    pub roughness: f32,
+    pub ao: f32,
+    // roughness in the green channel and metallic in the blue one, multiplied with the values above
+    pub metallic_roughness_map: Option<Rc<Texture>>,
+    pub ao_map: Option<Rc<Texture>>,
+
+    // for custom fragment shaders: bound to their own units and given to the sampler of that name,
+    // see set_custom_texture
+    custom_textures: Vec<(String, Rc<Texture>)>,
+}
+
+impl Material{
+    pub fn new() -> Self{
+        Material{
+            shading: Shading::Phong,
+
+            albedo: Vec4::ONE,
+            albedo_texture: None,
+            albedo_array: None,
+            albedo_layer: 0,
+            specular_strength: 0.3,
+            shininess: 32.,
+            specular_map: None,
+            emissive: Vec3::ZERO,
+            emissive_map: None,
+            normal_map: None,
+            double_sided: true,
+            transparent: false,
+
+            metallic: 0.,
+            roughness: 0.5,
+            ao: 1.,
+            metallic_roughness_map: None,
+            ao_map: None,
+
+            custom_textures: vec![],
+        }
+    }
+
+    pub fn pbr(albedo: Vec4, metallic: f32, roughness: f32) -> Self{
+        Material{
+            shading: Shading::Pbr,
+            albedo,
+            metallic,
+            roughness,
+            ..Material::new()
+        }
+    }
+
+    pub fn textured(texture: Rc<Texture>) -> Self{
+        Material{
+            albedo_texture: Some(texture),
+            ..Material::new()
+        }
+    }
+
+    // Gives `texture` to the sampler `name` of a custom fragment shader, replacing the one it had.
+    // Returns the unit it is bound to, or None (reported) when the units after FIRST_CUSTOM_TEXTURE_UNIT
+    // are all taken, 7 of them with the minimum GL guarantees
+    pub fn set_custom_texture(&mut self, name: &str, texture: Rc<Texture>) -> Option<u32>{
+        if let Some(index) = self.custom_textures.iter().position(|(custom_name, _)| custom_name == name){
+            self.custom_textures[index].1 = texture;
+            return Some(FIRST_CUSTOM_TEXTURE_UNIT + index as u32);
+        }
+
+        let unit = FIRST_CUSTOM_TEXTURE_UNIT + self.custom_textures.len() as u32;
+        if unit >= max_texture_units(){
+            println!("No texture unit left for custom texture {}, {} are taken already", name, self.custom_textures.len());
+            return None;
+        }
+        self.custom_textures.push((name.to_string(), texture));
+        Some(unit)
+    }
+
+    pub fn custom_textures(&self) -> &[(String, Rc<Texture>)]{
+        &self.custom_textures
+    }
+
+    // The program a mesh using `shader` should draw this material with: the same vertex stage
+    // with the fragment stage of the shading model, in the variant for the textures it has.
+    // Custom fragment shaders are left alone.
+    pub fn shader_for(&self, shader: &Rc<Shader>) -> Rc<Shader>{
+        if shader.fragment_path != LIT_FRAGMENT_SHADER && shader.fragment_path != PBR_FRAGMENT_SHADER{
+            return shader.clone();
+        }
+
+        let fragment_path = match self.shading{
+            Shading::Phong => LIT_FRAGMENT_SHADER,
+            Shading::Pbr => PBR_FRAGMENT_SHADER,
+        };
+        let defines = self.defines();
+        if shader.fragment_path == fragment_path && shader.defines == preprocessor::defines(&defines){
+            return shader.clone();
+        }
+        resources::shader_variant(&shader.vertex_path, fragment_path, &defines)
+    }
+
+    // what the lit shaders are compiled with for this material
+    fn defines(&self) -> Vec<(&'static str, &'static str)>{
+        let mut defines = vec![];
+        if self.albedo_texture.is_some(){
+            defines.push(("USE_TEXTURE", "1"));
+        }
+        if self.albedo_array.is_some(){
+            defines.push(("USE_TEXTURE_ARRAY", "1"));
+        }
+        defines
+    }
+
+    // Sends the material to the `material` uniform of a lit shader that is in use, binds
+    // its textures and sets the culling/blending state it needs
+    pub unsafe fn bind(&self, shader: &Shader){
+        // shaders without a material (lines) only get the render state
+        if shader.has_uniform("material.albedo"){
+            shader.set_uniform("material.albedo", self.albedo);
+            shader.set_uniform("material.emissive", self.emissive);
+            shader.set_uniform("material.useNormalMap", self.normal_map.is_some());
+            shader.set_uniform("material.useEmissiveMap", self.emissive_map.is_some());
+            shader.set_uniform("material.doubleSided", self.double_sided);
+
+            match self.shading{
+                Shading::Phong => {
+                    shader.set_uniform("material.specularStrength", self.specular_strength);
+                    shader.set_uniform("material.shininess", self.shininess);
+                    shader.set_uniform("material.useSpecularMap", self.specular_map.is_some());
+                }
+                Shading::Pbr => {
+                    shader.set_uniform("material.metallic", self.metallic);
+                    shader.set_uniform("material.roughness", self.roughness);
+                    shader.set_uniform("material.ao", self.ao);
+                    shader.set_uniform("material.useMetallicRoughnessMap", self.metallic_roughness_map.is_some());
+                    shader.set_uniform("material.useAoMap", self.ao_map.is_some());
+                }
+            }
+
+            let textures = [
+                ("albedoTexture", ALBEDO_TEXTURE_UNIT, &self.albedo_texture),
+                ("normalMap", NORMAL_MAP_UNIT, &self.normal_map),
+                ("metallicRoughnessMap", METALLIC_ROUGHNESS_MAP_UNIT, &self.metallic_roughness_map),
+                ("aoMap", AO_MAP_UNIT, &self.ao_map),
+                ("specularMap", SPECULAR_MAP_UNIT, &self.specular_map),
+                ("emissiveMap", EMISSIVE_MAP_UNIT, &self.emissive_map),
+            ];
+            for (name, unit, texture) in textures{
+                // The other shading model's samplers (and albedoTexture without USE_TEXTURE) aren't in the
+                // program. The maps are declared either way and only skipped at runtime by their use* flag,
+                // so the sampler of a missing map is simply left pointing at whatever unit it had
+                if texture.is_some() && shader.has_uniform(name){
+                    shader.set_uniform(name, unit as i32);
+                }
+                ActiveTexture(TEXTURE0 + unit);
+                BindTexture(TEXTURE_2D, texture.as_ref().map_or(0, |texture| texture.id));
+            }
+
+            if let Some(albedo_array) = &self.albedo_array{
+                shader.set_uniform("albedoTextureArray", ALBEDO_ARRAY_UNIT as i32);
+                // only in the program of meshes that aren't instanced
+                if shader.has_uniform("textureLayer"){
+                    shader.set_uniform("textureLayer", self.albedo_layer as i32);
+                }
+                ActiveTexture(TEXTURE0 + ALBEDO_ARRAY_UNIT);
+                BindTexture(TEXTURE_2D_ARRAY, albedo_array.id);
+            }
+            ActiveTexture(TEXTURE0);
+        }
+
+        for (i, (name, texture)) in self.custom_textures.iter().enumerate(){
+            let unit = FIRST_CUSTOM_TEXTURE_UNIT + i as u32;
+            if shader.has_uniform(name){
+                shader.set_uniform(name, unit as i32);
+            }
+            ActiveTexture(TEXTURE0 + unit);
+            BindTexture(TEXTURE_2D, texture.id);
+        }
+        ActiveTexture(TEXTURE0);
+
+        if self.double_sided{
+            Disable(CULL_FACE);
+        }
+        else{
+            Enable(CULL_FACE);
+        }
+
+        if self.transparent{
+            Enable(BLEND);
+            BlendFunc(SRC_ALPHA, ONE_MINUS_SRC_ALPHA);
+        }
+        else{
+            Disable(BLEND);
+        }
+    }
+}
+
+fn max_texture_units() -> u32{
+    if let Some(max_units) = MAX_TEXTURE_UNITS.get(){
+        return max_units;
+    }
+
+    let mut max_units = 0;
+    unsafe{
+        GetIntegerv(MAX_TEXTURE_IMAGE_UNITS, &mut max_units);
+    }
+    let max_units = max_units.max(0) as u32;
+    MAX_TEXTURE_UNITS.set(Some(max_units));
+    max_units
+}
@@ -1,29 +1,45 @@
-use std::{ffi::CString, ptr};
+use std::{ptr, rc::Rc};
 
 use gl::{*, types::*};
 use glam::{vec4, Mat4, Vec2, Vec3, Vec4};
 
-use crate::{bind_buffer, camera::{PROJ_MATRIX, VIEW_MATRIX}, gen_attrib_pointers, light::LIGHTS, shader::Shader, texture::make_tex, transform::Transform, vertex::Vertex, window};
+use crate::{bind_buffer, bounds::{Aabb, BoundingSphere}, gen_attrib_pointers, material::Material, resources, scene::Scene, shader::Shader, texture::Texture, transform::Transform, vertex::Vertex, window};
 
+// A mesh owns its vao/vbo/ebo and deletes them when dropped, while the shader and
+// material (with its textures) are shared handles that get freed when the last mesh using them goes away
 pub struct Mesh{
     pub vertices: Vec<Vertex>,
+    // replace through set_indices after setup_mesh, so update_mesh knows to upload them again
     pub indices: Vec<u32>,
-    pub shader: Shader,
+    pub shader: Rc<Shader>,
 
-    pub texture: u32,
+    pub material: Rc<Material>,
+
+    // in model space, kept up to date by new/setup_mesh/update_mesh (see compute_bounds)
+    pub aabb: Aabb,
+    pub bounding_sphere: BoundingSphere,
 
     pub vao: u32,
     pub vbo: u32,
     pub ebo: u32,
+
+    // STATIC_DRAW by default, use DYNAMIC_DRAW/STREAM_DRAW for meshes updated every frame
+    pub usage: GLenum,
+    vertex_capacity: usize,
+    index_capacity: usize,
+    indices_changed: bool,
 }
 
 impl Mesh{
-    pub fn new(vertices: Vec<Vertex>, indices: Vec<u32>, shader: Shader) -> Self{
+    pub fn new(vertices: Vec<Vertex>, indices: Vec<u32>, shader: Rc<Shader>) -> Self{
         let vbo = 0;
         let vao = 0;
         let ebo = 0;
 
-        let texture = 0;
+        let material = Rc::new(Material::new());
+
+        let aabb = Aabb::from_vertices(&vertices);
+        let bounding_sphere = BoundingSphere::from_vertices(&vertices, &aabb);
 
         let m = 
         Mesh {
@@ -31,61 +47,85 @@ impl Mesh{
             indices,
             shader,
 
-            texture,
+            material,
+
+            aabb,
+            bounding_sphere,
 
             vao,
             vbo,
             ebo,
+
+            usage: STATIC_DRAW,
+            vertex_capacity: 0,
+            index_capacity: 0,
+            indices_changed: false,
         };
         m
     }
 
-    pub fn set_texture(&mut self, texture: u32){
-        self.texture = texture;
+    pub fn set_usage(&mut self, usage: GLenum){
+        self.usage = usage;
+    }
+
+    pub fn set_material(&mut self, material: Rc<Material>){
+        self.shader = material.shader_for(&self.shader);
+        self.material = material;
+    }
+
+    pub fn set_texture(&mut self, texture: Rc<Texture>){
+        Rc::make_mut(&mut self.material).albedo_texture = Some(texture);
+        self.shader = self.material.shader_for(&self.shader);
+    }
+
+    // has to be called after changing the vertices without going through setup_mesh/update_mesh
+    pub fn compute_bounds(&mut self){
+        self.aabb = Aabb::from_vertices(&self.vertices);
+        self.bounding_sphere = BoundingSphere::from_vertices(&self.vertices, &self.aabb);
     }
 
     pub fn setup_mesh(&mut self){
+        self.compute_bounds();
         unsafe{
             GenVertexArrays(1, &mut self.vao);
             GenBuffers(1, &mut self.vbo);
 
             BindVertexArray(self.vao);
 
-            bind_buffer!(gl::ARRAY_BUFFER, self.vbo, self.vertices);
+            bind_buffer!(gl::ARRAY_BUFFER, self.vbo, self.vertices, self.usage);
             gen_attrib_pointers!(Vertex, 0 => position:3, 1 => color:4, 2 => tex_coords:2, 3 => normal: 3);
 
             GenBuffers(1, &mut self.ebo);
-            bind_buffer!(gl::ELEMENT_ARRAY_BUFFER, self.ebo, self.indices);
+            bind_buffer!(gl::ELEMENT_ARRAY_BUFFER, self.ebo, self.indices, self.usage);
             BindVertexArray(0);
         }
+
+        self.vertex_capacity = self.vertices.len();
+        self.index_capacity = self.indices.len();
+        self.indices_changed = false;
+    }
+
+    pub fn set_indices(&mut self, indices: Vec<u32>){
+        self.indices = indices;
+        self.indices_changed = true;
     }
 
     pub fn update_mesh(&mut self) {
-        // Bind the VAO and VBO
+        self.compute_bounds();
         unsafe {
             BindVertexArray(self.vao);
-            bind_buffer!(gl::ARRAY_BUFFER, self.vbo, self.vertices);
-    
-            // Only update the vertices (using glBufferSubData for dynamic updates)
-            gl::BufferSubData(
-                gl::ARRAY_BUFFER,
-                0,
-                (self.vertices.len() * std::mem::size_of::<Vertex>()) as isize,
-                self.vertices.as_ptr() as *const _,
-            );
-    
-            // If indices change, update them too
-            bind_buffer!(gl::ELEMENT_ARRAY_BUFFER, self.ebo, self.indices);
-            gl::BufferSubData(
-                gl::ELEMENT_ARRAY_BUFFER,
-                0,
-                (self.indices.len() * std::mem::size_of::<u32>()) as isize,
-                self.indices.as_ptr() as *const _,
-            );
-    
+
+            upload_buffer(gl::ARRAY_BUFFER, self.vbo, &self.vertices, &mut self.vertex_capacity, self.usage);
+
+            // the topology rarely changes (lines, cloth...), so the indices only go up after set_indices
+            if self.indices_changed{
+                upload_buffer(gl::ELEMENT_ARRAY_BUFFER, self.ebo, &self.indices, &mut self.index_capacity, self.usage);
+                self.indices_changed = false;
+            }
+
             BindVertexArray(0);
         }
-    }    
+    }
 
     pub fn translate(&mut self, change: Vec3){
         for i in self.vertices.iter_mut(){
@@ -134,41 +174,29 @@ impl Mesh{
     }
 
     pub fn set_shader(&mut self, vertexPath: &str, fragmentPath: &str){
-        self.shader = Shader::new(vertexPath, fragmentPath)
+        // the lit shaders still need the variant matching the material
+        self.shader = self.material.shader_for(&resources::shader(vertexPath, fragmentPath));
     }
 
     pub fn empty() -> Mesh{
         Mesh::new(vec![Vertex{position: Vec3::ZERO, color: Vec4::ONE, tex_coords: Vec2::ZERO, normal: Vec3::ZERO}],
             vec![0],
-            Shader::new("src/shaders/default_lit_shader.vs", "src/shaders/default_lit_shader.fs"))
+            resources::shader("src/shaders/default_lit_shader.vs", "src/shaders/default_lit_shader.fs"))
     }
 
     // TODO: implement this later!!!
     //pub fn set_shader(&mut self, )
 
-    pub fn draw(&self, view_position: Vec3, transform: Transform){
+    // the camera comes from the uniform blocks, see Scene::upload_frame_uniforms
+    pub fn draw(&self, transform: Transform, scene: &Scene){
         unsafe {
             self.shader.useProgram();
             BindVertexArray(self.vao);
 
-            self.shader.uniform_mat4fv(&CString::new("projection").expect("error when sending projection matrix to shader"), &PROJ_MATRIX.to_cols_array());
-            
-            self.shader.uniform_mat4fv(&CString::new("view").expect("error when sending view matrix to shader"), &VIEW_MATRIX.to_cols_array());
-
-            self.shader.setVector3(&CString::new("viewPos").expect("error when sending view position to shader"), &view_position);
-
-            self.shader.uniform_mat4fv(&CString::new("model").expect("error when sending model matrix to shader"), &get_model_matrix(transform).to_cols_array());
-
-            self.shader.setVector4(&CString::new("color").expect("error when sending mesh color to shader"), &self.vertices[0].color);
-            
-            let mut i = 0;
-            for light in LIGHTS.iter(){
-                self.shader.setVector3(&CString::new(format!("lightColor[{}]", i)).expect("error when sending light pos to shader"), &light.color);
-                self.shader.setVector3(&CString::new(format!("lightPos[{}]", i)).expect("error when sending light pos to shader"), &light.position);
-                i += 1;
-            }
+            self.shader.set_uniform("model", get_model_matrix(transform));
+            scene.set_uniforms(&self.shader);
 
-            BindTexture(gl::TEXTURE_2D, self.texture);
+            self.material.bind(&self.shader);
 
             DrawElements(gl::TRIANGLES, self.indices.len() as i32, gl::UNSIGNED_INT, ptr::null());
             BindVertexArray(0);
@@ -177,6 +205,45 @@ impl Mesh{
     }
 }
 
+impl Drop for Mesh{
+    fn drop(&mut self){
+        // meshes that were never set up have nothing on the GPU
+        unsafe{
+            if self.vao != 0{
+                DeleteVertexArrays(1, &self.vao);
+            }
+            if self.vbo != 0{
+                DeleteBuffers(1, &self.vbo);
+            }
+            if self.ebo != 0{
+                DeleteBuffers(1, &self.ebo);
+            }
+        }
+    }
+}
+
+// Writes `data` into `buffer`, only reallocating when it outgrows `capacity` (in elements).
+// Non static buffers are orphaned first, so the driver hands us fresh storage instead of
+// waiting for the draws of the previous frame that may still be reading the old one.
+unsafe fn upload_buffer<T>(buffer_type: GLenum, buffer: u32, data: &[T], capacity: &mut usize, usage: GLenum){
+    if data.is_empty(){
+        return;
+    }
+
+    BindBuffer(buffer_type, buffer);
+    let element_size = std::mem::size_of::<T>();
+
+    if data.len() > *capacity{
+        *capacity = if usage == STATIC_DRAW {data.len()} else {data.len().next_power_of_two()};
+        BufferData(buffer_type, (*capacity * element_size) as isize, ptr::null(), usage);
+    }
+    else if usage != STATIC_DRAW{
+        BufferData(buffer_type, (*capacity * element_size) as isize, ptr::null(), usage);
+    }
+
+    BufferSubData(buffer_type, 0, std::mem::size_of_val(data) as isize, data.as_ptr() as *const _);
+}
+
 pub fn get_model_matrix(transform: Transform) -> Mat4{
     let translation_matrix = Mat4::from_translation(transform.position);
     let rotation_matrix = Mat4::from_quat(transform.rotation);
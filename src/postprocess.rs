@@ -0,0 +1,231 @@
+use std::rc::Rc;
+
+use gl::{*, types::*};
+use glam::{vec2, Vec2, Vec3};
+
+use crate::{framebuffer::Framebuffer, resources, shader::Shader};
+
+const POST_VERTEX_SHADER: &str = "src/shaders/postprocess.vs";
+
+// the scene is drawn in floating point so lighting can go past 1 until ToneMapping
+const HDR_FORMAT: GLenum = RGBA16F;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum PostEffect{
+    // light bleeding around the parts brighter than `threshold`, blurred at half resolution.
+    // Works on the HDR colors so it goes before ToneMapping.
+    Bloom{ threshold: f32, intensity: f32, blur_passes: u32 },
+    // HDR to displayable colors (ACES filmic curve)
+    ToneMapping{ exposure: f32 },
+    // linear to display colors, usually 2.2
+    Gamma(f32),
+    // cheap edge antialiasing, works best on the final (tone mapped and gamma corrected) colors
+    Fxaa,
+    // darkens the corners, `radius` is where it starts (0.5 being the middle of the edges)
+    Vignette{ strength: f32, radius: f32 },
+}
+
+impl PostEffect{
+    pub fn bloom() -> Self{
+        PostEffect::Bloom { threshold: 1., intensity: 0.6, blur_passes: 4 }
+    }
+
+    pub fn tone_mapping() -> Self{
+        PostEffect::ToneMapping { exposure: 1. }
+    }
+
+    pub fn vignette() -> Self{
+        PostEffect::Vignette { strength: 0.4, radius: 0.45 }
+    }
+}
+
+struct PostShaders{
+    tone_mapping: Rc<Shader>,
+    gamma: Rc<Shader>,
+    fxaa: Rc<Shader>,
+    vignette: Rc<Shader>,
+    bright_pass: Rc<Shader>,
+    blur: Rc<Shader>,
+    bloom_combine: Rc<Shader>,
+}
+
+// The scene is drawn into an offscreen (multisampled) target between begin and end, which then
+// runs it through `effects` in order, the last one drawing to the window. With no effects the
+// image is just copied to the window.
+pub struct PostProcess{
+    pub effects: Vec<PostEffect>,
+
+    width: i32,
+    height: i32,
+    // what the scene is drawn into, resolved into `resolved` when multisampled
+    scene: Framebuffer,
+    resolved: Framebuffer,
+    // effects read from one and write to the other
+    ping_pong: [Framebuffer; 2],
+    // half resolution, for the bloom blur
+    bloom: [Framebuffer; 2],
+
+    shaders: PostShaders,
+    // the fullscreen triangle is made in the vertex shader, but core profile still wants a vao bound
+    vao: u32,
+}
+
+impl PostProcess{
+    pub fn new(width: i32, height: i32, samples: i32) -> Self{
+        let post_shader = |fragment_path: &str| resources::shader(POST_VERTEX_SHADER, fragment_path);
+        let mut vao = 0;
+        unsafe{
+            GenVertexArrays(1, &mut vao);
+        }
+
+        PostProcess{
+            effects: vec![],
+
+            width,
+            height,
+            scene: Framebuffer::new(width, height, samples, HDR_FORMAT),
+            resolved: Framebuffer::color_only(width, height, HDR_FORMAT),
+            ping_pong: [Framebuffer::color_only(width, height, HDR_FORMAT), Framebuffer::color_only(width, height, HDR_FORMAT)],
+            bloom: [Framebuffer::color_only(width / 2, height / 2, HDR_FORMAT), Framebuffer::color_only(width / 2, height / 2, HDR_FORMAT)],
+
+            shaders: PostShaders{
+                tone_mapping: post_shader("src/shaders/post_tonemap.fs"),
+                gamma: post_shader("src/shaders/post_gamma.fs"),
+                fxaa: post_shader("src/shaders/post_fxaa.fs"),
+                vignette: post_shader("src/shaders/post_vignette.fs"),
+                bright_pass: post_shader("src/shaders/post_bright.fs"),
+                blur: post_shader("src/shaders/post_blur.fs"),
+                bloom_combine: post_shader("src/shaders/post_bloom_combine.fs"),
+            },
+            vao,
+        }
+    }
+
+    // for the FramebufferSize event
+    pub fn resize(&mut self, width: i32, height: i32){
+        self.width = width;
+        self.height = height;
+
+        self.scene.resize(width, height);
+        self.resolved.resize(width, height);
+        for framebuffer in self.ping_pong.iter_mut(){
+            framebuffer.resize(width, height);
+        }
+        for framebuffer in self.bloom.iter_mut(){
+            framebuffer.resize(width / 2, height / 2);
+        }
+    }
+
+    // binds and clears the scene target, everything drawn until `end` goes through the effects
+    pub fn begin(&self, clear_color: Vec3){
+        self.scene.bind();
+        unsafe{
+            ClearColor(clear_color.x, clear_color.y, clear_color.z, 1.);
+            Clear(COLOR_BUFFER_BIT | DEPTH_BUFFER_BIT);
+        }
+    }
+
+    // applies the effects and draws the result to the window
+    pub fn end(&self){
+        let mut input = &self.scene;
+        if self.scene.multisampled(){
+            self.scene.blit_to(&self.resolved);
+            input = &self.resolved;
+        }
+
+        if self.effects.is_empty(){
+            input.blit_to_default(self.width, self.height);
+            return;
+        }
+
+        unsafe{
+            Disable(DEPTH_TEST);
+            Disable(BLEND);
+            Disable(CULL_FACE);
+            BindVertexArray(self.vao);
+
+            for (i, effect) in self.effects.iter().enumerate(){
+                let target = if i + 1 == self.effects.len() {None} else {Some(&self.ping_pong[i % 2])};
+                self.apply(effect, input, target);
+                if let Some(target) = target{
+                    input = target;
+                }
+            }
+
+            BindVertexArray(0);
+            UseProgram(0);
+            Enable(DEPTH_TEST);
+        }
+    }
+
+    unsafe fn apply(&self, effect: &PostEffect, input: &Framebuffer, target: Option<&Framebuffer>){
+        let shaders = &self.shaders;
+        match *effect{
+            PostEffect::Bloom { threshold, intensity, blur_passes } => {
+                self.pass(&shaders.bright_pass, input, Some(&self.bloom[0]), |shader| {
+                    shader.set_uniform("threshold", threshold);
+                });
+                for _ in 0..blur_passes{
+                    for (from, to, direction) in [(0, 1, vec2(1., 0.)), (1, 0, vec2(0., 1.))]{
+                        self.pass(&shaders.blur, &self.bloom[from], Some(&self.bloom[to]), |shader| {
+                            shader.set_uniform("direction", direction);
+                        });
+                    }
+                }
+                self.pass(&shaders.bloom_combine, input, target, |shader| {
+                    shader.set_uniform("intensity", intensity);
+                    shader.set_uniform("bloomTexture", 1);
+                    ActiveTexture(TEXTURE1);
+                    BindTexture(TEXTURE_2D, self.bloom[0].color);
+                    ActiveTexture(TEXTURE0);
+                });
+            }
+            PostEffect::ToneMapping { exposure } => {
+                self.pass(&shaders.tone_mapping, input, target, |shader| {
+                    shader.set_uniform("exposure", exposure);
+                });
+            }
+            PostEffect::Gamma(gamma) => {
+                self.pass(&shaders.gamma, input, target, |shader| {
+                    shader.set_uniform("gamma", gamma);
+                });
+            }
+            PostEffect::Fxaa => {
+                self.pass(&shaders.fxaa, input, target, |_| {});
+            }
+            PostEffect::Vignette { strength, radius } => {
+                self.pass(&shaders.vignette, input, target, |shader| {
+                    shader.set_uniform("strength", strength);
+                    shader.set_uniform("radius", radius);
+                });
+            }
+        }
+    }
+
+    // one fullscreen draw of `shader` reading `input` (screenTexture, on unit 0) into `target`, or the window when None
+    unsafe fn pass(&self, shader: &Shader, input: &Framebuffer, target: Option<&Framebuffer>, set_uniforms: impl FnOnce(&Shader)){
+        match target{
+            Some(target) => target.bind(),
+            None => Framebuffer::bind_default(self.width, self.height),
+        }
+
+        shader.useProgram();
+        ActiveTexture(TEXTURE0);
+        BindTexture(TEXTURE_2D, input.color);
+        shader.set_uniform("screenTexture", 0);
+        if shader.has_uniform("texelSize"){
+            shader.set_uniform("texelSize", Vec2::ONE / vec2(input.width as f32, input.height as f32));
+        }
+        set_uniforms(shader);
+
+        DrawArrays(TRIANGLES, 0, 3);
+    }
+}
+
+impl Drop for PostProcess{
+    fn drop(&mut self){
+        unsafe{
+            DeleteVertexArrays(1, &self.vao);
+        }
+    }
+}
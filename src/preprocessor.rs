@@ -0,0 +1,165 @@
+use std::{fs, io};
+
+use crate::{light::MAX_LIGHTS, shader::ShaderError, shadow::{MAX_SHADOW_LAYERS, SHADOW_CASCADES}};
+
+// where `#include "file"` is looked up
+pub const SHADER_DIR: &str = "src/shaders";
+
+// name/value pairs turned into `#define name value` lines, sorted so the same set always
+// compares (and caches) equal whatever order it was given in
+pub type Defines = Vec<(String, String)>;
+
+pub fn defines(pairs: &[(&str, &str)]) -> Defines{
+    let mut defines: Defines = pairs.iter().map(|(name, value)| (name.to_string(), value.to_string())).collect();
+    defines.sort();
+    defines.dedup_by(|a, b| a.0 == b.0);
+    defines
+}
+
+// Defines every shader gets, so the limits in the GLSL code always match the ones on the Rust side
+pub fn engine_defines() -> Defines{
+    defines(&[
+        ("MAX_LIGHTS", &MAX_LIGHTS.to_string()),
+        ("SHADOW_CASCADES", &SHADOW_CASCADES.to_string()),
+        ("MAX_SHADOW_LAYERS", &MAX_SHADOW_LAYERS.to_string()),
+    ])
+}
+
+// A shader stage after preprocessing, with every file that went into it (for hot reloading)
+pub struct Source{
+    pub code: String,
+    pub files: Vec<String>,
+}
+
+// Resolves the `#include "file"` lines (relative to SHADER_DIR, each file is only included once)
+// and adds the engine defines and `defines` right after the `#version` line.
+// `#line` directives keep compile errors pointing at the right line, the source string number
+// they report is the index of the file in `Source::files`.
+pub fn preprocess(path: &str, defines: &Defines) -> Result<Source, ShaderError>{
+    preprocess_in(SHADER_DIR, path, defines)
+}
+
+fn preprocess_in(include_dir: &str, path: &str, defines: &Defines) -> Result<Source, ShaderError>{
+    let code = read(path)?;
+    let mut source = Source { code: String::new(), files: vec![path.to_string()] };
+
+    let mut lines = code.lines();
+    let mut first_line = 1;
+    if code.trim_start().starts_with("#version"){
+        for line in lines.by_ref(){
+            source.code.push_str(line);
+            source.code.push('\n');
+            first_line += 1;
+            if line.trim_start().starts_with("#version"){
+                break;
+            }
+        }
+    }
+
+    for (name, value) in engine_defines().iter().chain(defines.iter()){
+        source.code.push_str(&format!("#define {} {}\n", name, value));
+    }
+    source.code.push_str(&format!("#line {} 0\n", first_line));
+
+    expand(include_dir, path, lines, first_line, 0, &mut source)?;
+    Ok(source)
+}
+
+fn expand<'a>(include_dir: &str, path: &str, lines: impl Iterator<Item = &'a str>, first_line: usize, file_index: usize, source: &mut Source) -> Result<(), ShaderError>{
+    for (number, line) in lines.enumerate().map(|(i, line)| (first_line + i, line)){
+        let Some(include) = line.trim_start().strip_prefix("#include") else{
+            source.code.push_str(line);
+            source.code.push('\n');
+            continue;
+        };
+
+        let include = include.trim();
+        if include.len() < 2 || !include.starts_with('"') || !include.ends_with('"'){
+            let error = io::Error::new(io::ErrorKind::InvalidData, format!("line {}: expected #include \"file\"", number));
+            return Err(ShaderError::Io { path: path.to_string(), error });
+        }
+
+        let include_path = format!("{}/{}", include_dir, &include[1..include.len() - 1]);
+        if source.files.contains(&include_path){
+            // an empty line in its place, so the lines after it keep their numbers
+            source.code.push('\n');
+            continue;
+        }
+        let code = read(&include_path)?;
+        source.files.push(include_path.clone());
+        let include_index = source.files.len() - 1;
+
+        source.code.push_str(&format!("#line 1 {}\n", include_index));
+        expand(include_dir, &include_path, code.lines(), 1, include_index, source)?;
+        // back to the including file, on the line after the #include
+        source.code.push_str(&format!("#line {} {}\n", number + 1, file_index));
+    }
+    Ok(())
+}
+
+fn read(path: &str) -> Result<String, ShaderError>{
+    fs::read_to_string(path).map_err(|error| ShaderError::Io { path: path.to_string(), error })
+}
+
+#[cfg(test)]
+mod tests{
+    use super::*;
+
+    // writes the files into a directory of their own and preprocesses the first one
+    fn preprocess_files(test: &str, files: &[(&str, &str)], defines: &Defines) -> Source{
+        let dir = std::env::temp_dir().join(format!("preprocessor_{}_{}", test, std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        for (name, code) in files{
+            fs::write(dir.join(name), code).unwrap();
+        }
+        let dir = dir.to_str().unwrap();
+        let source = preprocess_in(dir, &format!("{}/{}", dir, files[0].0), defines).unwrap();
+        fs::remove_dir_all(dir).unwrap();
+        source
+    }
+
+    // the code without the engine defines, whose values change with the limits
+    fn without_engine_defines(code: &str) -> Vec<&str>{
+        let engine = engine_defines();
+        code.lines().filter(|line| !engine.iter().any(|(name, _)| line.starts_with(&format!("#define {} ", name)))).collect()
+    }
+
+    #[test]
+    fn includes_every_file_once(){
+        let source = preprocess_files("once", &[
+            ("main.fs", "#include \"common.glsl\"\n#include \"lights.glsl\"\n#include \"common.glsl\"\nvoid main(){}\n"),
+            ("common.glsl", "float common;\n"),
+            ("lights.glsl", "#include \"common.glsl\"\nfloat lights;\n"),
+        ], &Defines::new());
+
+        assert_eq!(source.code.matches("float common;").count(), 1);
+        assert_eq!(source.code.matches("float lights;").count(), 1);
+        let files: Vec<&str> = source.files.iter().map(|file| file.rsplit('/').next().unwrap()).collect();
+        assert_eq!(files, ["main.fs", "common.glsl", "lights.glsl"]);
+    }
+
+    #[test]
+    fn line_directives_follow_the_files(){
+        let source = preprocess_files("lines", &[
+            ("main.fs", "#version 330 core\n#include \"a.glsl\"\n\n#include \"b.glsl\"\nvoid main(){}\n"),
+            ("a.glsl", "float a;\n"),
+            ("b.glsl", "#include \"a.glsl\"\nfloat b;\n"),
+        ], &defines(&[("USE_TEXTURE", "1")]));
+
+        assert_eq!(without_engine_defines(&source.code), [
+            "#version 330 core",
+            "#define USE_TEXTURE 1",
+            "#line 2 0",
+            "#line 1 1",
+            "float a;",
+            "#line 3 0",
+            "",
+            "#line 1 2",
+            // the second include of a.glsl, skipped
+            "",
+            "float b;",
+            "#line 5 0",
+            "void main(){}",
+        ]);
+    }
+}
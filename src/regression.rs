@@ -0,0 +1,284 @@
+use std::{fs, path::Path, rc::Rc};
+
+use gl::*;
+use glam::{vec3, vec4, Vec3, Vec4};
+use image::{Rgba, RgbaImage};
+
+use crate::{camera::Camera, capture, cubemap::Cubemap, framebuffer::Framebuffer, game_object::GameObject, headless::HeadlessContext, light::Light, material::Material, mesh::Mesh, render_queue::RenderQueue, scene::Scene, shapes::{make_shape, Shapes}, skybox::Skybox, transform::Transform};
+
+// Image regression tests: a few fixed scenes are rendered headless (see HeadlessContext) and
+// compared to the golden images in GOLDEN_DIR, catching unintended changes to the lit shaders,
+// make_shape or the camera on machines without a GPU. Part of `cargo test`, skipped (with a
+// message) where Mesa's EGL isn't installed; `UPDATE_GOLDEN=1 cargo test golden` (re)records the
+// goldens after an intended change.
+
+const GOLDEN_DIR: &str = "tests/golden";
+// the rendered image and a diff of every failing scene end up here
+const OUTPUT_DIR: &str = "regression_output";
+
+const WIDTH: u32 = 320;
+const HEIGHT: u32 = 240;
+
+// Software rasterizers differ slightly in precision, so pixels may be off by a bit and a few
+// (edges, mostly) may be off by more
+#[derive(Clone, Copy, Debug)]
+pub struct Tolerance{
+    // largest difference of a channel (0-255) for a pixel to still count as equal
+    pub channel: u8,
+    // fraction of the pixels that may be different
+    pub pixels: f32,
+}
+
+impl Default for Tolerance{
+    fn default() -> Self{
+        Tolerance { channel: 3, pixels: 0.002 }
+    }
+}
+
+#[derive(Debug)]
+pub struct Comparison{
+    pub different_pixels: usize,
+    pub total_pixels: usize,
+    pub max_channel_difference: u8,
+    // red where the pixels are different, the golden image dimmed elsewhere
+    pub diff: RgbaImage,
+}
+
+impl Comparison{
+    pub fn passes(&self, tolerance: Tolerance) -> bool{
+        self.different_pixels as f32 <= self.total_pixels as f32 * tolerance.pixels
+    }
+}
+
+// None when the sizes don't match
+pub fn compare(actual: &RgbaImage, golden: &RgbaImage, tolerance: Tolerance) -> Option<Comparison>{
+    if actual.dimensions() != golden.dimensions(){
+        return None;
+    }
+
+    let mut diff = RgbaImage::new(golden.width(), golden.height());
+    let mut different_pixels = 0;
+    let mut max_channel_difference = 0;
+    for ((actual, golden), diff) in actual.pixels().zip(golden.pixels()).zip(diff.pixels_mut()){
+        let difference = (0..4).map(|channel| actual[channel].abs_diff(golden[channel])).max().unwrap_or(0);
+        max_channel_difference = max_channel_difference.max(difference);
+
+        *diff = if difference > tolerance.channel{
+            different_pixels += 1;
+            Rgba([255, 0, 0, 255])
+        }
+        else{
+            Rgba([golden[0] / 4, golden[1] / 4, golden[2] / 4, 255])
+        };
+    }
+
+    Some(Comparison{
+        different_pixels,
+        total_pixels: (golden.width() * golden.height()) as usize,
+        max_channel_difference,
+        diff,
+    })
+}
+
+struct RegressionScene{
+    name: &'static str,
+    camera_position: Vec3,
+    // yaw, pitch in degrees
+    camera_angles: (f32, f32),
+    shadows: bool,
+    // panorama for the skybox and environment
+    sky: Option<&'static str>,
+    build: fn() -> Vec<GameObject<Mesh>>,
+}
+
+fn shape(shape: Shapes, position: Vec3, scale: f32, color: Vec4) -> GameObject<Mesh>{
+    let mut object = GameObject::<Mesh>::new(make_shape(shape, Transform::new(), color));
+    object.set_position(position);
+    object.scale(scale);
+    object.set_color(color);
+    object.setup_mesh();
+    object
+}
+
+fn scenes() -> Vec<RegressionScene>{
+    vec![
+        RegressionScene{
+            name: "lit_shapes",
+            camera_position: vec3(0., 0., 4.),
+            camera_angles: (-90., 0.),
+            shadows: false,
+            sky: None,
+            build: || vec![
+                shape(Shapes::Cube, vec3(-1.5, 0., 0.), 0.8, vec4(1., 0.2, 0.2, 1.)),
+                shape(Shapes::Sphere, vec3(0., 0., 0.), 0.6, vec4(0.2, 1., 0.2, 1.)),
+                shape(Shapes::Quad, vec3(1.5, 0., 0.), 0.8, vec4(0.2, 0.2, 1., 1.)),
+            ],
+        },
+        RegressionScene{
+            name: "shadows_from_above",
+            camera_position: vec3(0., 3., 5.),
+            camera_angles: (-90., -30.),
+            shadows: true,
+            sky: None,
+            build: || {
+                let mut floor = shape(Shapes::Cube, vec3(0., -1., 0.), 1., Vec4::ONE);
+                floor.scale3D(vec3(6., 0.1, 6.));
+                vec![
+                    floor,
+                    shape(Shapes::Sphere, vec3(0., 0., 0.), 0.5, vec4(1., 0.8, 0.2, 1.)),
+                ]
+            },
+        },
+        RegressionScene{
+            name: "transparent_in_front",
+            camera_position: vec3(0., 0., 3.),
+            camera_angles: (-90., 0.),
+            shadows: false,
+            sky: None,
+            build: || vec![
+                shape(Shapes::Cube, vec3(0., 0., -1.), 0.7, vec4(0.2, 0.4, 1., 1.)),
+                shape(Shapes::Quad, vec3(0.3, 0., 0.), 0.6, vec4(1., 1., 1., 0.5)),
+            ],
+        },
+        RegressionScene{
+            name: "skybox_reflections",
+            camera_position: vec3(0., 0.5, 3.),
+            camera_angles: (-90., -5.),
+            shadows: false,
+            sky: Some("src/textures/sky.png"),
+            build: || {
+                let mut mirror = shape(Shapes::Sphere, vec3(0., 0.5, 0.), 0.8, Vec4::ONE);
+                mirror.set_material(Rc::new(Material::pbr(Vec4::ONE, 1., 0.1)));
+                vec![mirror]
+            },
+        },
+    ]
+}
+
+// draws a scene offscreen, the way Window does with no post effects, and reads it back
+fn render(regression_scene: &RegressionScene) -> RgbaImage{
+    let objects = (regression_scene.build)();
+
+    let mut scene = Scene::new();
+    let mut sun = Light::directional(vec3(-0.3, -1., -0.5), vec3(1., 1., 1.));
+    sun.cast_shadows = regression_scene.shadows;
+    scene.add_light(sun);
+    scene.add_light(Light::point(regression_scene.camera_position, vec3(1., 1., 1.)));
+    if regression_scene.shadows{
+        scene.enable_shadows(1024);
+    }
+    if let Some(sky) = regression_scene.sky{
+        let sky = Rc::new(Cubemap::from_equirectangular(sky, 128).expect("sky panorama"));
+        scene.skybox = Some(Skybox::new(sky.clone()));
+        scene.environment = Some(sky);
+    }
+
+    let (width, height) = (WIDTH as f32, HEIGHT as f32);
+    let mut camera = Camera::new();
+    camera.position = regression_scene.camera_position;
+    (camera.yaw, camera.pitch) = regression_scene.camera_angles;
+    camera.update_camera_vectors();
+    camera.update_matrix(width, height);
+
+    scene.render_shadows(&camera, width, height, |pass| {
+        for object in objects.iter(){
+            pass.draw_object(object);
+        }
+    });
+    // no time, so nothing animated can make the image differ
+    scene.upload_frame_uniforms(&camera, 0.);
+
+    let target = Framebuffer::new(WIDTH as i32, HEIGHT as i32, 4, RGBA8);
+    let resolved = Framebuffer::color_only(WIDTH as i32, HEIGHT as i32, RGBA8);
+    target.bind();
+    unsafe{
+        // what Window::new sets up
+        Enable(DEPTH_TEST);
+        Enable(TEXTURE_CUBE_MAP_SEAMLESS);
+        ClearColor(0.1, 0.2, 0.3, 1.);
+        Clear(COLOR_BUFFER_BIT | DEPTH_BUFFER_BIT);
+    }
+
+    let mut render_queue = RenderQueue::new();
+    for object in objects.iter(){
+        render_queue.push(object);
+    }
+    render_queue.draw(&scene, camera.position);
+
+    target.blit_to(&resolved);
+    capture::read_framebuffer(resolved.fbo, WIDTH, HEIGHT)
+}
+
+// Renders every scene and compares it to its golden image, printing the result of each.
+// With `update` the goldens are overwritten instead. Returns whether all of them passed.
+fn run(update: bool) -> bool{
+    let tolerance = Tolerance::default();
+    let mut all_passed = true;
+    for regression_scene in scenes(){
+        let rendered = render(&regression_scene);
+        let golden_path = Path::new(GOLDEN_DIR).join(format!("{}.png", regression_scene.name));
+
+        if update{
+            let saved = fs::create_dir_all(GOLDEN_DIR).map_err(image::ImageError::from).and_then(|_| rendered.save(&golden_path));
+            match saved{
+                Ok(()) => println!("{}: recorded {}", regression_scene.name, golden_path.display()),
+                Err(error) => {
+                    println!("{}: failed to record {}: {}", regression_scene.name, golden_path.display(), error);
+                    all_passed = false;
+                }
+            }
+            continue;
+        }
+
+        let golden = match image::open(&golden_path){
+            Ok(golden) => golden.to_rgba8(),
+            Err(error) => {
+                println!("{}: FAILED, no golden image at {} ({}), record it with UPDATE_GOLDEN=1", regression_scene.name, golden_path.display(), error);
+                all_passed = false;
+                continue;
+            }
+        };
+
+        let passed = match compare(&rendered, &golden, tolerance){
+            Some(comparison) => {
+                let passed = comparison.passes(tolerance);
+                println!("{}: {}, {} of {} pixels different (max channel difference {})", regression_scene.name,
+                    if passed {"ok"} else {"FAILED"}, comparison.different_pixels, comparison.total_pixels, comparison.max_channel_difference);
+                if !passed{
+                    save_output(&comparison.diff, &format!("{}.diff.png", regression_scene.name));
+                }
+                passed
+            }
+            None => {
+                println!("{}: FAILED, rendered {:?} but the golden image is {:?}", regression_scene.name, rendered.dimensions(), golden.dimensions());
+                false
+            }
+        };
+
+        if !passed{
+            save_output(&rendered, &format!("{}.actual.png", regression_scene.name));
+            all_passed = false;
+        }
+    }
+    all_passed
+}
+
+fn save_output(output: &RgbaImage, file_name: &str){
+    let path = Path::new(OUTPUT_DIR).join(file_name);
+    if let Err(error) = fs::create_dir_all(OUTPUT_DIR).map_err(image::ImageError::from).and_then(|_| output.save(&path)){
+        println!("Failed to save {}: {}", path.display(), error);
+    }
+}
+
+#[test]
+fn golden_images(){
+    let _context = match HeadlessContext::new(){
+        Ok(context) => context,
+        Err(error) => {
+            println!("Skipping the golden image comparison, no headless GL context: {}", error);
+            return;
+        }
+    };
+
+    assert!(run(std::env::var_os("UPDATE_GOLDEN").is_some()), "rendered images differ from the goldens, see {}", OUTPUT_DIR);
+}
@@ -0,0 +1,231 @@
+use std::{ptr, rc::Rc};
+
+use gl::*;
+use glam::{Mat4, Vec3};
+
+use crate::{bounds::{Aabb, BoundingSphere, Frustum}, camera::{PROJ_MATRIX, VIEW_MATRIX}, game_object::GameObject, instanced::InstancedMesh, line_batch::LineBatch, material::Material, mesh::{get_model_matrix, Mesh}, scene::Scene, shader::Shader, transform::Transform};
+
+enum DrawKind<'a>{
+    Mesh(&'a Mesh, Mat4),
+    Instanced(&'a InstancedMesh),
+}
+
+struct DrawCommand<'a>{
+    kind: DrawKind<'a>,
+    // world space bounds, for culling and ordering the transparent draws (by the center)
+    sphere: BoundingSphere,
+    aabb: Option<Aabb>,
+}
+
+impl DrawCommand<'_>{
+    // the sphere test is cheaper and usually enough, the box is tighter for long thin meshes
+    fn visible(&self, frustum: &Frustum) -> bool{
+        frustum.intersects_sphere(&self.sphere) && self.aabb.is_none_or(|aabb| frustum.intersects_aabb(&aabb))
+    }
+
+    fn shader(&self) -> &Rc<Shader>{
+        match self.kind{
+            DrawKind::Mesh(mesh, _) => &mesh.shader,
+            DrawKind::Instanced(instanced) => &instanced.shader,
+        }
+    }
+
+    fn material(&self) -> &Rc<Material>{
+        match self.kind{
+            DrawKind::Mesh(mesh, _) => &mesh.material,
+            DrawKind::Instanced(instanced) => &instanced.material,
+        }
+    }
+
+    fn vao(&self) -> u32{
+        match self.kind{
+            DrawKind::Mesh(mesh, _) => mesh.vao,
+            DrawKind::Instanced(instanced) => instanced.vao,
+        }
+    }
+}
+
+// how many of the pushed draws were outside of the camera frustum
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct RenderStats{
+    pub drawn: usize,
+    pub culled: usize,
+}
+
+// Collects the draws of a frame and issues them in an order that changes as little GL state as
+// possible: opaque draws grouped by shader, then material, then mesh, then the line batches and the scene's skybox,
+// followed by the transparent ones from back to front with blending on and depth writes off
+// (they are still depth tested).
+pub struct RenderQueue<'a>{
+    opaque: Vec<DrawCommand<'a>>,
+    transparent: Vec<DrawCommand<'a>>,
+    lines: Vec<&'a LineBatch>,
+}
+
+impl<'a> RenderQueue<'a>{
+    pub fn new() -> Self{
+        RenderQueue{
+            opaque: vec![],
+            transparent: vec![],
+            lines: vec![],
+        }
+    }
+
+    // transparent when its material is, or when its color has some transparency (see GameObject::set_color)
+    pub fn push(&mut self, object: &'a GameObject<Mesh>){
+        let transparent = object.object.material.transparent || object.color.w < 1.;
+        self.push_mesh(&object.object, object.transform, transparent);
+    }
+
+    pub fn push_mesh(&mut self, mesh: &'a Mesh, transform: Transform, transparent: bool){
+        // nothing on the GPU yet
+        if mesh.vao == 0{
+            return;
+        }
+
+        let model = get_model_matrix(transform);
+        let command = DrawCommand{
+            kind: DrawKind::Mesh(mesh, model),
+            sphere: mesh.bounding_sphere.transformed(model),
+            aabb: Some(mesh.aabb.transformed(model)),
+        };
+        if transparent{
+            self.transparent.push(command);
+        }
+        else{
+            self.opaque.push(command);
+        }
+    }
+
+    // the instances have to be uploaded already (see InstancedMesh::upload_instances). Instanced
+    // meshes are culled and ordered as a whole, with a sphere around all of their instances.
+    pub fn push_instanced(&mut self, instanced: &'a InstancedMesh){
+        if instanced.instance_count() == 0{
+            return;
+        }
+
+        let mesh_sphere = instanced.mesh.bounding_sphere;
+        let sphere = instanced.instances.iter()
+            .map(|instance| mesh_sphere.transformed(instance.model))
+            .reduce(|sphere, instance_sphere| sphere.merged(&instance_sphere))
+            .unwrap_or(mesh_sphere);
+
+        // one see-through instance makes the whole draw blended, like the alpha of GameObject colors does
+        let transparent = instanced.material.transparent || instanced.instances.iter().any(|instance| instance.color.w < 1.);
+        let command = DrawCommand { kind: DrawKind::Instanced(instanced), sphere, aabb: None };
+        if transparent{
+            self.transparent.push(command);
+        }
+        else{
+            self.opaque.push(command);
+        }
+    }
+
+    // Drawn, without culling, right after the opaque draws so transparent surfaces blend over them.
+    // The segments have to be uploaded already (see LineBatch::upload).
+    pub fn push_lines(&mut self, lines: &'a LineBatch){
+        self.lines.push(lines);
+    }
+
+    // Draws everything that was pushed and is in view of the camera (PROJ_MATRIX * VIEW_MATRIX),
+    // then empties the queue. The frame uniforms have to be uploaded already (see Scene::upload_frame_uniforms).
+    pub fn draw(&mut self, scene: &Scene, view_position: Vec3) -> RenderStats{
+        let frustum = Frustum::from_matrix(unsafe { PROJ_MATRIX * VIEW_MATRIX });
+        let pushed = self.opaque.len() + self.transparent.len();
+        self.opaque.retain(|command| command.visible(&frustum));
+        self.transparent.retain(|command| command.visible(&frustum));
+        let drawn = self.opaque.len() + self.transparent.len();
+        let stats = RenderStats { drawn, culled: pushed - drawn };
+
+        self.opaque.sort_by_key(|command| (command.shader().ID.get(), Rc::as_ptr(command.material()) as usize, command.vao()));
+
+        let distance = |command: &DrawCommand| command.sphere.center.distance_squared(view_position);
+        self.transparent.sort_by(|a, b| distance(b).total_cmp(&distance(a)));
+
+        let mut state = DrawState::default();
+        unsafe{
+            for command in self.opaque.iter(){
+                state.draw(command, scene, false);
+            }
+
+            if !self.lines.is_empty(){
+                for lines in self.lines.iter(){
+                    lines.draw();
+                }
+                // they used their own program and vao
+                state = DrawState::default();
+            }
+
+            if let Some(skybox) = &scene.skybox{
+                skybox.draw();
+                // it used its own program and vao
+                state = DrawState::default();
+            }
+
+            // rebinds the first material, so blending gets turned on even if it was the last opaque one
+            state.material = 0;
+            DepthMask(FALSE);
+            for command in self.transparent.iter(){
+                state.draw(command, scene, true);
+            }
+            DepthMask(TRUE);
+            Disable(BLEND);
+
+            BindVertexArray(0);
+            UseProgram(0);
+        }
+
+        self.opaque.clear();
+        self.transparent.clear();
+        self.lines.clear();
+        stats
+    }
+}
+
+// what is currently bound, to skip redundant changes
+#[derive(Default)]
+struct DrawState{
+    shader: u32,
+    material: usize,
+    vao: u32,
+}
+
+impl DrawState{
+    unsafe fn draw(&mut self, command: &DrawCommand, scene: &Scene, transparent: bool){
+        let shader = command.shader();
+        let material = command.material();
+
+        let shader_changed = shader.ID.get() != self.shader;
+        if shader_changed{
+            shader.useProgram();
+            scene.set_uniforms(shader);
+            self.shader = shader.ID.get();
+        }
+
+        // material uniforms belong to the program, a new program needs them again
+        if shader_changed || Rc::as_ptr(material) as usize != self.material{
+            material.bind(shader);
+            if transparent{
+                // bind() only blends transparent materials, transparent colors need it too
+                Enable(BLEND);
+                BlendFunc(SRC_ALPHA, ONE_MINUS_SRC_ALPHA);
+            }
+            self.material = Rc::as_ptr(material) as usize;
+        }
+
+        if command.vao() != self.vao{
+            BindVertexArray(command.vao());
+            self.vao = command.vao();
+        }
+
+        match command.kind{
+            DrawKind::Mesh(mesh, model) => {
+                shader.set_uniform("model", model);
+                DrawElements(TRIANGLES, mesh.indices.len() as i32, UNSIGNED_INT, ptr::null());
+            }
+            DrawKind::Instanced(instanced) => {
+                DrawElementsInstanced(TRIANGLES, instanced.mesh.indices.len() as i32, UNSIGNED_INT, ptr::null(), instanced.instance_count() as i32);
+            }
+        }
+    }
+}
@@ -0,0 +1,141 @@
+use std::{cell::{Cell, RefCell}, collections::HashMap, rc::{Rc, Weak}, time::{Duration, Instant}};
+
+use glam::Vec4;
+use image::ImageResult;
+
+use crate::{mesh::Mesh, preprocessor::{self, Defines}, shader::{Shader, ShaderError}, shapes::{make_shape, Shapes}, texture::{make_tex, Texture, TextureDescriptor}, transform::Transform};
+
+// Caches for GPU resources that end up being loaded over and over with the same arguments.
+// The caches only keep weak references: the handles (Rc) own the GL objects, so the
+// objects are deleted when the last handle is dropped and get recreated if asked for again.
+// GL objects belong to the context of the thread that created them, hence thread locals.
+thread_local! {
+    static SHADERS: RefCell<HashMap<(String, String, Defines), Weak<Shader>>> = RefCell::new(HashMap::new());
+    static TEXTURES: RefCell<HashMap<(String, TextureDescriptor), Weak<Texture>>> = RefCell::new(HashMap::new());
+    static SHAPES: RefCell<HashMap<Shapes, Weak<Mesh>>> = RefCell::new(HashMap::new());
+    static LAST_SHADER_CHECK: Cell<Option<Instant>> = const { Cell::new(None) };
+}
+
+// how often watch_shaders looks at the shader files
+const SHADER_CHECK_INTERVAL: Duration = Duration::from_millis(500);
+
+pub type ShaderHandle = Rc<Shader>;
+pub type TextureHandle = Rc<Texture>;
+pub type MeshHandle = Rc<Mesh>;
+
+pub const DEFAULT_VERTEX_SHADER: &str = "src/shaders/default_lit_shader.vs";
+pub const DEFAULT_FRAGMENT_SHADER: &str = "src/shaders/default_lit_shader.fs";
+pub const DEFAULT_TEXTURE: &str = "src/textures/default_tex.png";
+
+pub fn try_shader(vertex_path: &str, fragment_path: &str) -> Result<ShaderHandle, ShaderError>{
+    try_shader_variant(vertex_path, fragment_path, &[])
+}
+
+// The program built from the same files with some `#define`s added (see preprocessor), every
+// define set is a separate program in the cache
+pub fn try_shader_variant(vertex_path: &str, fragment_path: &str, defines: &[(&str, &str)]) -> Result<ShaderHandle, ShaderError>{
+    let key = (vertex_path.to_string(), fragment_path.to_string(), preprocessor::defines(defines));
+    if let Some(shader) = SHADERS.with(|cache| cache.borrow().get(&key).and_then(Weak::upgrade)){
+        return Ok(shader);
+    }
+
+    // failures aren't cached, asking again retries the files
+    let shader = Rc::new(Shader::with_defines(vertex_path, fragment_path, &key.2)?);
+    SHADERS.with(|cache| {
+        let mut cache = cache.borrow_mut();
+        cache.retain(|_, value| value.strong_count() > 0);
+        cache.insert(key, Rc::downgrade(&shader));
+    });
+    Ok(shader)
+}
+
+pub fn shader(vertex_path: &str, fragment_path: &str) -> ShaderHandle{
+    shader_variant(vertex_path, fragment_path, &[])
+}
+
+// Like try_shader_variant, but a shader that fails to load is reported and replaced by the default
+// lit shader, so the object still shows up. Only panics if the default shader itself is broken.
+pub fn shader_variant(vertex_path: &str, fragment_path: &str, defines: &[(&str, &str)]) -> ShaderHandle{
+    match try_shader_variant(vertex_path, fragment_path, defines){
+        Ok(shader) => shader,
+        Err(error) if vertex_path == DEFAULT_VERTEX_SHADER && fragment_path == DEFAULT_FRAGMENT_SHADER && defines.is_empty() => {
+            panic!("{}", error);
+        }
+        Err(error) => {
+            println!("{}\nfalling back to the default shader", error);
+            shader(DEFAULT_VERTEX_SHADER, DEFAULT_FRAGMENT_SHADER)
+        }
+    }
+}
+
+// Development helper meant to be called every frame: recompiles the cached shaders whose
+// files changed on disk. A shader that fails to compile keeps its previous program and
+// prints the error log, so it can be fixed while the app keeps running.
+pub fn watch_shaders(){
+    let now = Instant::now();
+    if LAST_SHADER_CHECK.get().is_some_and(|last| now.duration_since(last) < SHADER_CHECK_INTERVAL){
+        return;
+    }
+    LAST_SHADER_CHECK.set(Some(now));
+
+    let shaders: Vec<ShaderHandle> = SHADERS.with(|cache| cache.borrow().values().filter_map(Weak::upgrade).collect());
+    for shader in shaders{
+        if shader.changed_on_disk(){
+            match shader.reload(){
+                Ok(()) => println!("reloaded {} / {}", shader.vertex_path, shader.fragment_path),
+                Err(error) => println!("{}\nkeeping the previous program", error),
+            }
+        }
+    }
+}
+
+pub fn texture(path: &str) -> TextureHandle{
+    texture_with(path, &TextureDescriptor::default())
+}
+
+// every descriptor is a separate texture in the cache
+pub fn try_texture(path: &str, descriptor: &TextureDescriptor) -> ImageResult<TextureHandle>{
+    let key = (path.to_string(), *descriptor);
+    if let Some(texture) = TEXTURES.with(|cache| cache.borrow().get(&key).and_then(Weak::upgrade)){
+        return Ok(texture);
+    }
+
+    // failures aren't cached, asking again retries the file
+    let texture = Rc::new(make_tex(path, descriptor)?);
+    TEXTURES.with(|cache| {
+        let mut cache = cache.borrow_mut();
+        cache.retain(|_, value| value.strong_count() > 0);
+        cache.insert(key, Rc::downgrade(&texture));
+    });
+    Ok(texture)
+}
+
+// Like try_texture, but a texture that fails to load is reported and replaced by the default
+// one, so the object still shows up. Only panics if the default texture itself can't be loaded.
+pub fn texture_with(path: &str, descriptor: &TextureDescriptor) -> TextureHandle{
+    match try_texture(path, descriptor){
+        Ok(texture) => texture,
+        Err(error) if path == DEFAULT_TEXTURE => {
+            panic!("Failed to load the default texture {}: {}", path, error);
+        }
+        Err(error) => {
+            println!("Failed to load texture {}: {}\nfalling back to the default texture", path, error);
+            texture_with(DEFAULT_TEXTURE, descriptor)
+        }
+    }
+}
+
+// unit sized, white, already uploaded mesh of a shape, meant to be drawn with a transform
+pub fn shape(shape: Shapes) -> MeshHandle{
+    // built outside of the borrow since making a shape asks for its shader from this module
+    if let Some(mesh) = SHAPES.with(|cache| cache.borrow().get(&shape).and_then(Weak::upgrade)){
+        return mesh;
+    }
+
+    let mut mesh = make_shape(shape, Transform::new(), Vec4::ONE);
+    mesh.setup_mesh();
+    let mesh = Rc::new(mesh);
+
+    SHAPES.with(|cache| cache.borrow_mut().insert(shape, Rc::downgrade(&mesh)));
+    mesh
+}
@@ -0,0 +1,94 @@
+use std::rc::Rc;
+
+use gl::*;
+
+use crate::{camera::{Camera, PROJ_MATRIX, VIEW_MATRIX}, cubemap::Cubemap, light::{lights_block, Light}, material::ENVIRONMENT_MAP_UNIT, shader::Shader, shadow::{ShadowMaps, ShadowPass}, skybox::Skybox, uniform_buffer::{CameraBlock, LightsBlock, UniformBuffer, CAMERA_BLOCK_BINDING, LIGHTS_BLOCK_BINDING}};
+
+// Everything shared by the objects drawn in a frame that isn't owned by any of them
+pub struct Scene{
+    pub lights: Vec<Light>,
+    // no shadows at all when None
+    pub shadow_maps: Option<ShadowMaps>,
+    // cube map lighting (and reflected by) Pbr materials, usually the skybox's
+    pub environment: Option<Rc<Cubemap>>,
+    pub environment_intensity: f32,
+    // drawn behind everything instead of the clear color, see Skybox
+    pub skybox: Option<Skybox>,
+
+    // per frame data shared by every program, see upload_frame_uniforms
+    camera_buffer: UniformBuffer<CameraBlock>,
+    lights_buffer: UniformBuffer<LightsBlock>,
+}
+
+impl Scene{
+    pub fn new() -> Self{
+        Scene{
+            lights: vec![],
+            shadow_maps: None,
+            environment: None,
+            environment_intensity: 1.,
+            skybox: None,
+
+            camera_buffer: UniformBuffer::new(CAMERA_BLOCK_BINDING),
+            lights_buffer: UniformBuffer::new(LIGHTS_BLOCK_BINDING),
+        }
+    }
+
+    // Uploads the camera and the lights to the uniform blocks every program reads them from.
+    // Once a frame, after render_shadows (the shadow matrices are part of the lights) and
+    // before drawing anything.
+    pub fn upload_frame_uniforms(&self, camera: &Camera, time: f32){
+        let (projection, view) = unsafe { (PROJ_MATRIX, VIEW_MATRIX) };
+        self.camera_buffer.upload(&CameraBlock{
+            projection,
+            view,
+            view_position: camera.position,
+            time,
+        });
+        self.lights_buffer.upload(&lights_block(self));
+    }
+
+    // Binds the shadow maps and the environment of a lit shader that is in use and points
+    // its samplers at them, shaders without lighting are left alone
+    pub unsafe fn set_uniforms(&self, shader: &Shader){
+        if !shader.has_uniform("shadowMaps"){
+            return;
+        }
+
+        // the shadow sampler always gets its own unit, two sampler types can't share one
+        shader.set_uniform("shadowMaps", 1);
+        if let Some(shadow_maps) = &self.shadow_maps{
+            shadow_maps.bind();
+        }
+
+        // only the Pbr shader uses the environment
+        if !shader.has_uniform("environmentMap"){
+            return;
+        }
+        shader.set_uniform("useEnvironment", self.environment.is_some());
+        shader.set_uniform("environmentIntensity", self.environment_intensity);
+        shader.set_uniform("environmentMap", ENVIRONMENT_MAP_UNIT as i32);
+        ActiveTexture(TEXTURE0 + ENVIRONMENT_MAP_UNIT);
+        BindTexture(TEXTURE_CUBE_MAP, self.environment.as_ref().map_or(0, |cubemap| cubemap.id));
+        ActiveTexture(TEXTURE0);
+    }
+
+    pub fn enable_shadows(&mut self, size: i32){
+        let mut shadow_maps = ShadowMaps::new(size);
+        shadow_maps.setup();
+        self.shadow_maps = Some(shadow_maps);
+    }
+
+    // has to happen before drawing the objects that receive the shadows, see ShadowMaps::render
+    pub fn render_shadows(&mut self, camera: &Camera, w: f32, h: f32, draw_casters: impl FnMut(&ShadowPass)){
+        if let Some(shadow_maps) = &mut self.shadow_maps{
+            shadow_maps.render(&self.lights, camera, w, h, draw_casters);
+        }
+    }
+
+    // returns the index of the light, to change it later through `lights`
+    pub fn add_light(&mut self, light: Light) -> usize{
+        self.lights.push(light);
+        self.lights.len() - 1
+    }
+}
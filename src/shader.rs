@@ -1,199 +1,492 @@
+use std::cell::{Cell, RefCell};
+use std::collections::{HashMap, HashSet};
 use std::ffi::{CString, CStr};
-use std::fs::File;
-use std::io::Read;
+use std::fmt;
+use std::fs;
+use std::io;
 use std::ptr;
-use std::str;
+use std::time::SystemTime;
 
-use gl::{self, GetUniformLocation, UniformMatrix4fv, FALSE};
+use gl::{self, FALSE};
 use gl::types::*;
-use glam::{vec3, Vec3, Vec4};
+use glam::{Mat3, Mat4, Vec2, Vec3, Vec4};
 
-#[derive(Debug, Eq, PartialEq, Copy, Clone)]
+use crate::preprocessor::{self, Defines};
+use crate::uniform_buffer;
+
+#[derive(Debug, Eq, PartialEq)]
 pub struct Shader {
-    pub ID: u32,
+    // a Cell so the program can be swapped by reload() while meshes hold it through an Rc
+    pub ID: Cell<u32>,
+    // where the program came from, to build variants of it (see Material::shader_for) and reload it
+    pub vertex_path: String,
+    pub fragment_path: String,
+    pub geometry_path: Option<String>,
+    /// the `#define`s this variant was built with, see preprocessor::preprocess
+    pub defines: Defines,
+    /// the stage files plus everything they #include, all watched for changes
+    files: RefCell<Vec<String>>,
+    modified: Cell<Option<SystemTime>>,
+    /// locations of the active uniforms, read once after linking
+    uniforms: RefCell<HashMap<String, GLint>>,
+    /// names set_uniform was asked for that the program doesn't have, so each is only reported once
+    unknown_uniforms: RefCell<HashSet<String>>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ShaderStage {
+    Vertex,
+    Fragment,
+    Geometry,
+}
+
+impl ShaderStage {
+    fn gl_type(&self) -> GLenum {
+        match self {
+            ShaderStage::Vertex => gl::VERTEX_SHADER,
+            ShaderStage::Fragment => gl::FRAGMENT_SHADER,
+            ShaderStage::Geometry => gl::GEOMETRY_SHADER,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum ShaderError {
+    /// a source file couldn't be read
+    Io { path: String, error: io::Error },
+    /// a stage didn't compile, `log` is the driver's info log. Its source string numbers
+    /// (the `#line` ones) are indices into `files`, the stage file and what it #included
+    Compile { path: String, stage: ShaderStage, log: String, files: Vec<String> },
+    /// the stages compiled but couldn't be linked together
+    Link { log: String },
+}
+
+impl fmt::Display for ShaderError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ShaderError::Io { path, error } => write!(f, "failed to read shader {}: {}", path, error),
+            ShaderError::Compile { path, stage, log, files } => {
+                write!(f, "failed to compile {:?} shader {}:\n{}", stage, path, log)?;
+                // only worth listing when something was included
+                if files.len() > 1 {
+                    write!(f, "\nsource strings in the log:")?;
+                    for (index, file) in files.iter().enumerate() {
+                        write!(f, "\n  {}: {}", index, file)?;
+                    }
+                }
+                Ok(())
+            }
+            ShaderError::Link { log } => write!(f, "failed to link shader program:\n{}", log),
+        }
+    }
+}
+
+impl std::error::Error for ShaderError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ShaderError::Io { error, .. } => Some(error),
+            _ => None,
+        }
+    }
 }
 
 /// NOTE: mixture of `shader_s.h` and `shader_m.h` (the latter just contains
 /// a few more setters for uniforms)
 #[allow(dead_code)]
 impl Shader {
-    pub fn new(vertexPath: &str, fragmentPath: &str) -> Shader {
-        let mut shader = Shader { ID: 0 };
-        // 1. retrieve the vertex/fragment source code from filesystem
-        let mut vShaderFile = File::open(vertexPath)
-            .unwrap_or_else(|_| panic!("Failed to open {}", vertexPath));
-        let mut fShaderFile = File::open(fragmentPath)
-            .unwrap_or_else(|_| panic!("Failed to open {}", fragmentPath));
-        let mut vertexCode = String::new();
-        let mut fragmentCode = String::new();
-        vShaderFile
-            .read_to_string(&mut vertexCode)
-            .expect("Failed to read vertex shader");
-        fShaderFile
-            .read_to_string(&mut fragmentCode)
-            .expect("Failed to read fragment shader");
-
-        let vShaderCode = CString::new(vertexCode.as_bytes()).unwrap();
-        let fShaderCode = CString::new(fragmentCode.as_bytes()).unwrap();
-
-        // 2. compile shaders
+    pub fn new(vertexPath: &str, fragmentPath: &str) -> Result<Shader, ShaderError> {
+        Shader::with_defines(vertexPath, fragmentPath, &Defines::new())
+    }
+
+    /// builds a variant of the program with `defines` added to both stages
+    /// ------------------------------------------------------------------------
+    pub fn with_defines(vertexPath: &str, fragmentPath: &str, defines: &Defines) -> Result<Shader, ShaderError> {
+        let shader = Shader {
+            ID: Cell::new(0),
+            vertex_path: vertexPath.to_string(),
+            fragment_path: fragmentPath.to_string(),
+            geometry_path: None,
+            defines: defines.clone(),
+            files: RefCell::new(vec![]),
+            modified: Cell::new(None),
+            uniforms: RefCell::new(HashMap::new()),
+            unknown_uniforms: RefCell::new(HashSet::new()),
+        };
+
+        let (ID, files) = build_program(&shader.stages(), defines)?;
+        shader.set_program(ID, files);
+
+        Ok(shader)
+    }
+
+    /// true when one of the source files was modified after the program was built
+    /// ------------------------------------------------------------------------
+    pub fn changed_on_disk(&self) -> bool {
+        last_modified(&self.files.borrow()) > self.modified.get()
+    }
+
+    /// rebuilds the program from its source files and swaps it in place, so every mesh
+    /// sharing this shader picks it up. If anything fails the current program is kept.
+    /// ------------------------------------------------------------------------
+    pub fn reload(&self) -> Result<(), ShaderError> {
+        // a failed build isn't retried until something changes again
+        self.modified.set(last_modified(&self.files.borrow()));
+
+        let (ID, files) = build_program(&self.stages(), &self.defines)?;
+        unsafe {
+            gl::DeleteProgram(self.ID.get());
+        }
+        // the includes and the uniforms may have changed too
+        self.set_program(ID, files);
+
+        Ok(())
+    }
+
+    /// the source file of every stage the program is made of
+    fn stages(&self) -> Vec<(ShaderStage, &str)> {
+        let mut stages = vec![(ShaderStage::Vertex, self.vertex_path.as_str()), (ShaderStage::Fragment, self.fragment_path.as_str())];
+        if let Some(geometry_path) = &self.geometry_path {
+            stages.push((ShaderStage::Geometry, geometry_path.as_str()));
+        }
+        stages
+    }
+
+    fn set_program(&self, ID: u32, files: Vec<String>) {
+        self.ID.set(ID);
+        self.modified.set(last_modified(&files));
+        self.files.replace(files);
         unsafe {
-            // vertex shader
-            let vertex = gl::CreateShader(gl::VERTEX_SHADER);
-            gl::ShaderSource(vertex, 1, &vShaderCode.as_ptr(), ptr::null());
-            gl::CompileShader(vertex);
-            shader.checkCompileErrors(vertex, "VERTEX");
-            // fragment Shader
-            let fragment = gl::CreateShader(gl::FRAGMENT_SHADER);
-            gl::ShaderSource(fragment, 1, &fShaderCode.as_ptr(), ptr::null());
-            gl::CompileShader(fragment);
-            shader.checkCompileErrors(fragment, "FRAGMENT");
-            // shader Program
-            let ID = gl::CreateProgram();
-            gl::AttachShader(ID, vertex);
-            gl::AttachShader(ID, fragment);
-            gl::LinkProgram(ID);
-            shader.checkCompileErrors(ID, "PROGRAM");
-            // delete the shaders as they're linked into our program now and no longer necessary
-            gl::DeleteShader(vertex);
-            gl::DeleteShader(fragment);
-            shader.ID = ID;
-        }
-
-        shader
+            uniform_buffer::bind_blocks(ID);
+            self.uniforms.replace(active_uniforms(ID));
+        }
+        self.unknown_uniforms.borrow_mut().clear();
     }
 
     /// activate the shader
     /// ------------------------------------------------------------------------
     pub unsafe fn useProgram(&self) {
-        gl::UseProgram(self.ID)
+        gl::UseProgram(self.ID.get())
+    }
+
+    /// sets a uniform of the program, which must be in use. The location comes from the
+    /// cache, names the program doesn't have (or that the compiler optimized out) are
+    /// reported the first time and ignored.
+    /// ------------------------------------------------------------------------
+    pub unsafe fn set_uniform<T: Uniform>(&self, name: &str, value: T) {
+        if let Some(location) = self.location(name) {
+            value.set(location);
+        }
+    }
+
+    /// for uniforms only some of the programs a value is sent to have
+    /// ------------------------------------------------------------------------
+    pub fn has_uniform(&self, name: &str) -> bool {
+        self.uniforms.borrow().contains_key(name)
+    }
+
+    fn location(&self, name: &str) -> Option<GLint> {
+        if let Some(location) = self.uniforms.borrow().get(name) {
+            return Some(*location);
+        }
+        if self.unknown_uniforms.borrow_mut().insert(name.to_string()) {
+            println!("{} / {} has no active uniform `{}`", self.vertex_path, self.fragment_path, name);
+        }
+        None
     }
 
     /// utility uniform functions
     /// ------------------------------------------------------------------------
     pub unsafe fn setBool(&self, name: &CStr, value: bool) {
-        gl::Uniform1i(gl::GetUniformLocation(self.ID, name.as_ptr()), value as i32);
+        self.set_uniform(&name.to_string_lossy(), value);
     }
     /// ------------------------------------------------------------------------
     pub unsafe fn setInt(&self, name: &CStr, value: i32) {
-        gl::Uniform1i(gl::GetUniformLocation(self.ID, name.as_ptr()), value);
+        self.set_uniform(&name.to_string_lossy(), value);
     }
     /// ------------------------------------------------------------------------
     pub unsafe fn setFloat(&self, name: &CStr, value: f32) {
-        gl::Uniform1f(gl::GetUniformLocation(self.ID, name.as_ptr()), value);
+        self.set_uniform(&name.to_string_lossy(), value);
     }
     /// ------------------------------------------------------------------------
     pub unsafe fn setVector3(&self, name: &CStr, value: &Vec3) {
-        gl::Uniform3f(gl::GetUniformLocation(self.ID, name.as_ptr()), value.x, value.y, value.z);
+        self.set_uniform(&name.to_string_lossy(), *value);
     }
     /// ------------------------------------------------------------------------
     pub unsafe fn setVec3(&self, name: &CStr, x: f32, y: f32, z: f32) {
-        gl::Uniform3f(gl::GetUniformLocation(self.ID, name.as_ptr()), x, y, z);
+        self.set_uniform(&name.to_string_lossy(), Vec3::new(x, y, z));
     }
     /// ------------------------------------------------------------------------
     pub unsafe fn setVector4(&self, name: &CStr, value: &Vec4) {
-        gl::Uniform4f(gl::GetUniformLocation(self.ID, name.as_ptr()), value.x, value.y, value.z, value.w);
+        self.set_uniform(&name.to_string_lossy(), *value);
     }
 
     /// ------------------------------------------------------------------------
     pub unsafe fn uniform_mat4fv(&self, name: &CStr, mat: &[f32; 16]) {
-        UniformMatrix4fv(
-            GetUniformLocation(self.ID, name.as_ptr()), 
-            1, 
-            FALSE, 
-            mat as *const f32
-        );
+        self.set_uniform(&name.to_string_lossy(), Mat4::from_cols_array(mat));
     }
 
 
-    /// utility function for checking shader compilation/linking errors.
-    /// ------------------------------------------------------------------------
-    unsafe fn checkCompileErrors(&self, shader: u32, type_: &str) {
-        let mut success = gl::FALSE as GLint;
-        let mut infoLog = Vec::with_capacity(1024);
-        infoLog.set_len(1024 - 1); // subtract 1 to skip the trailing null character
-        if type_ != "PROGRAM" {
-            gl::GetShaderiv(shader, gl::COMPILE_STATUS, &mut success);
-            if success != gl::TRUE as GLint {
-                gl::GetShaderInfoLog(shader, 1024, ptr::null_mut(), infoLog.as_mut_ptr() as *mut GLchar);
-                println!("ERROR::SHADER_COMPILATION_ERROR of type: {}\n{}\n \
-                          -- --------------------------------------------------- -- ",
-                         type_,
-                         str::from_utf8(&infoLog).unwrap());
+    /// Only used in 4.9 Geometry shaders - ignore until then (shader.h in original C++)
+    pub fn with_geometry_shader(vertexPath: &str, fragmentPath: &str, geometryPath: &str) -> Result<Self, ShaderError> {
+        let shader = Shader {
+            ID: Cell::new(0),
+            vertex_path: vertexPath.to_string(),
+            fragment_path: fragmentPath.to_string(),
+            geometry_path: Some(geometryPath.to_string()),
+            defines: Defines::new(),
+            files: RefCell::new(vec![]),
+            modified: Cell::new(None),
+            uniforms: RefCell::new(HashMap::new()),
+            unknown_uniforms: RefCell::new(HashSet::new()),
+        };
+
+        let (ID, files) = build_program(&shader.stages(), &shader.defines)?;
+        shader.set_program(ID, files);
+
+        Ok(shader)
+    }
+}
+
+impl Drop for Shader {
+    fn drop(&mut self) {
+        unsafe {
+            gl::DeleteProgram(self.ID.get());
+        }
+    }
+}
+
+/// reads, preprocesses, compiles and links the stages into a new program. Nothing is leaked on failure.
+/// Also returns every file that went into it.
+fn build_program(stages: &[(ShaderStage, &str)], defines: &Defines) -> Result<(u32, Vec<String>), ShaderError> {
+    // 1. retrieve the source code from filesystem, with the includes and defines resolved
+    let mut sources = vec![];
+    let mut files: Vec<String> = vec![];
+    for (stage, path) in stages {
+        let source = preprocessor::preprocess(path, defines)?;
+        let code = CString::new(source.code).map_err(|error| ShaderError::Io { path: path.to_string(), error: io::Error::new(io::ErrorKind::InvalidData, error) })?;
+        for file in source.files.iter() {
+            if !files.contains(file) {
+                files.push(file.clone());
             }
+        }
+        sources.push((*stage, *path, code, source.files));
+    }
+
+    // 2. compile shaders
+    unsafe {
+        let mut compiled = vec![];
+        for (stage, path, code, stage_files) in sources.iter() {
+            let object = gl::CreateShader(stage.gl_type());
+            gl::ShaderSource(object, 1, &code.as_ptr(), ptr::null());
+            gl::CompileShader(object);
+            compiled.push(object);
+
+            if let Err(log) = check_compile_errors(object, false) {
+                for object in compiled {
+                    gl::DeleteShader(object);
+                }
+                return Err(ShaderError::Compile { path: path.to_string(), stage: *stage, log, files: stage_files.clone() });
+            }
+        }
+
+        // shader Program
+        let ID = gl::CreateProgram();
+        for object in compiled.iter() {
+            gl::AttachShader(ID, *object);
+        }
+        gl::LinkProgram(ID);
+        // delete the shaders as they're linked into our program now and no longer necessary
+        for object in compiled {
+            gl::DeleteShader(object);
+        }
+
+        if let Err(log) = check_compile_errors(ID, true) {
+            gl::DeleteProgram(ID);
+            return Err(ShaderError::Link { log });
+        }
+
+        Ok((ID, files))
+    }
+}
+
+/// name -> location of every active uniform of a linked program. Arrays are reported by
+/// their first element, every element gets an entry and the bare name points at the first one.
+/// Uniforms inside uniform blocks have no location and are left out.
+unsafe fn active_uniforms(program: u32) -> HashMap<String, GLint> {
+    let mut count = 0;
+    let mut max_length = 0;
+    gl::GetProgramiv(program, gl::ACTIVE_UNIFORMS, &mut count);
+    gl::GetProgramiv(program, gl::ACTIVE_UNIFORM_MAX_LENGTH, &mut max_length);
+
+    let location = |name: &str| {
+        let name = CString::new(name).expect("uniform names can't contain null characters");
+        gl::GetUniformLocation(program, name.as_ptr())
+    };
+
+    let mut uniforms = HashMap::new();
+    let mut buffer = vec![0u8; max_length.max(1) as usize];
+    for index in 0..count.max(0) as u32 {
+        let mut length = 0;
+        let mut size = 0;
+        let mut kind = 0;
+        gl::GetActiveUniform(program, index, buffer.len() as GLsizei, &mut length, &mut size, &mut kind, buffer.as_mut_ptr() as *mut GLchar);
+        let name = String::from_utf8_lossy(&buffer[..length.max(0) as usize]).to_string();
 
-        } else {
-            gl::GetProgramiv(shader, gl::LINK_STATUS, &mut success);
-            if success != gl::TRUE as GLint {
-                gl::GetProgramInfoLog(shader, 1024, ptr::null_mut(), infoLog.as_mut_ptr() as *mut GLchar);
-                println!("ERROR::PROGRAM_LINKING_ERROR of type: {}\n{}\n \
-                          -- --------------------------------------------------- -- ",
-                         type_,
-                         str::from_utf8(&infoLog).unwrap());
+        let first = location(&name);
+        if first < 0 {
+            continue;
+        }
+        if let Some(base) = name.strip_suffix("[0]") {
+            // element locations don't have to be consecutive, each one is asked for
+            for element in 1..size {
+                let element = format!("{}[{}]", base, element);
+                let element_location = location(&element);
+                uniforms.insert(element, element_location);
             }
+            uniforms.insert(base.to_string(), first);
         }
+        uniforms.insert(name, first);
+    }
+    uniforms
+}
 
+/// utility function for checking shader compilation/linking errors, returns the info log on failure.
+unsafe fn check_compile_errors(object: u32, program: bool) -> Result<(), String> {
+    let mut success = gl::FALSE as GLint;
+    let mut log_length = 0;
+    if program {
+        gl::GetProgramiv(object, gl::LINK_STATUS, &mut success);
+        gl::GetProgramiv(object, gl::INFO_LOG_LENGTH, &mut log_length);
+    } else {
+        gl::GetShaderiv(object, gl::COMPILE_STATUS, &mut success);
+        gl::GetShaderiv(object, gl::INFO_LOG_LENGTH, &mut log_length);
+    }
+    if success == gl::TRUE as GLint {
+        return Ok(());
     }
 
-    /// Only used in 4.9 Geometry shaders - ignore until then (shader.h in original C++)
-    pub fn with_geometry_shader(vertexPath: &str, fragmentPath: &str, geometryPath: &str) -> Self {
-        let mut shader = Shader { ID: 0 };
-        // 1. retrieve the vertex/fragment source code from filesystem
-        let mut vShaderFile = File::open(vertexPath)
-            .unwrap_or_else(|_| panic!("Failed to open {}", vertexPath));
-        let mut fShaderFile = File::open(fragmentPath)
-            .unwrap_or_else(|_| panic!("Failed to open {}", fragmentPath));
-        let mut gShaderFile = File::open(geometryPath)
-            .unwrap_or_else(|_| panic!("Failed to open {}", geometryPath));
-        let mut vertexCode = String::new();
-        let mut fragmentCode = String::new();
-        let mut geometryCode = String::new();
-        vShaderFile
-            .read_to_string(&mut vertexCode)
-            .expect("Failed to read vertex shader");
-        fShaderFile
-            .read_to_string(&mut fragmentCode)
-            .expect("Failed to read fragment shader");
-        gShaderFile
-            .read_to_string(&mut geometryCode)
-            .expect("Failed to read geometry shader");
-
-        let vShaderCode = CString::new(vertexCode.as_bytes()).unwrap();
-        let fShaderCode = CString::new(fragmentCode.as_bytes()).unwrap();
-        let gShaderCode = CString::new(geometryCode.as_bytes()).unwrap();
-
-        // 2. compile shaders
-        unsafe {
-            // vertex shader
-            let vertex = gl::CreateShader(gl::VERTEX_SHADER);
-            gl::ShaderSource(vertex, 1, &vShaderCode.as_ptr(), ptr::null());
-            gl::CompileShader(vertex);
-            shader.checkCompileErrors(vertex, "VERTEX");
-            // fragment Shader
-            let fragment = gl::CreateShader(gl::FRAGMENT_SHADER);
-            gl::ShaderSource(fragment, 1, &fShaderCode.as_ptr(), ptr::null());
-            gl::CompileShader(fragment);
-            shader.checkCompileErrors(fragment, "FRAGMENT");
-            // geometry shader
-            let geometry = gl::CreateShader(gl::GEOMETRY_SHADER);
-            gl::ShaderSource(geometry, 1, &gShaderCode.as_ptr(), ptr::null());
-            gl::CompileShader(geometry);
-            shader.checkCompileErrors(geometry, "GEOMETRY");
-
-            // shader Program
-            let ID = gl::CreateProgram();
-            gl::AttachShader(ID, vertex);
-            gl::AttachShader(ID, fragment);
-            gl::AttachShader(ID, geometry);
-            gl::LinkProgram(ID);
-            shader.checkCompileErrors(ID, "PROGRAM");
-            // delete the shaders as they're linked into our program now and no longer necessary
-            gl::DeleteShader(vertex);
-            gl::DeleteShader(fragment);
-            gl::DeleteShader(geometry);
-            shader.ID = ID;
-        }
-
-        shader
+    // only read what the driver actually wrote, the length includes the trailing null character
+    let mut info_log = vec![0u8; log_length.max(1) as usize];
+    let mut written = 0;
+    if program {
+        gl::GetProgramInfoLog(object, info_log.len() as GLsizei, &mut written, info_log.as_mut_ptr() as *mut GLchar);
+    } else {
+        gl::GetShaderInfoLog(object, info_log.len() as GLsizei, &mut written, info_log.as_mut_ptr() as *mut GLchar);
+    }
+    info_log.truncate(written.max(0) as usize);
+
+    Err(String::from_utf8_lossy(&info_log).trim_end().to_string())
+}
+
+/// most recent modification time of a set of files, None if one can't be read
+fn last_modified<P: AsRef<str>>(paths: &[P]) -> Option<SystemTime> {
+    let mut latest = None;
+    for path in paths {
+        let modified = fs::metadata(path.as_ref()).and_then(|metadata| metadata.modified()).ok()?;
+        latest = latest.max(Some(modified));
+    }
+    latest
+}
+
+/// A value Shader::set_uniform can send, slices and arrays go to array uniforms.
+pub trait Uniform {
+    unsafe fn set(self, location: GLint);
+}
+
+impl Uniform for bool {
+    unsafe fn set(self, location: GLint) {
+        gl::Uniform1i(location, self as i32);
+    }
+}
+
+impl Uniform for i32 {
+    unsafe fn set(self, location: GLint) {
+        gl::Uniform1i(location, self);
+    }
+}
+
+impl Uniform for f32 {
+    unsafe fn set(self, location: GLint) {
+        gl::Uniform1f(location, self);
+    }
+}
+
+impl Uniform for Vec2 {
+    unsafe fn set(self, location: GLint) {
+        gl::Uniform2f(location, self.x, self.y);
+    }
+}
+
+impl Uniform for Vec3 {
+    unsafe fn set(self, location: GLint) {
+        gl::Uniform3f(location, self.x, self.y, self.z);
+    }
+}
+
+impl Uniform for Vec4 {
+    unsafe fn set(self, location: GLint) {
+        gl::Uniform4f(location, self.x, self.y, self.z, self.w);
+    }
+}
+
+impl Uniform for Mat3 {
+    unsafe fn set(self, location: GLint) {
+        gl::UniformMatrix3fv(location, 1, FALSE, self.to_cols_array().as_ptr());
+    }
+}
+
+impl Uniform for Mat4 {
+    unsafe fn set(self, location: GLint) {
+        gl::UniformMatrix4fv(location, 1, FALSE, self.to_cols_array().as_ptr());
+    }
+}
+
+impl Uniform for &[i32] {
+    unsafe fn set(self, location: GLint) {
+        gl::Uniform1iv(location, self.len() as GLsizei, self.as_ptr());
+    }
+}
+
+impl Uniform for &[f32] {
+    unsafe fn set(self, location: GLint) {
+        gl::Uniform1fv(location, self.len() as GLsizei, self.as_ptr());
+    }
+}
+
+// the glam types are plain floats laid out one after the other, so slices of them can be sent as is
+
+impl Uniform for &[Vec2] {
+    unsafe fn set(self, location: GLint) {
+        gl::Uniform2fv(location, self.len() as GLsizei, self.as_ptr() as *const f32);
+    }
+}
+
+impl Uniform for &[Vec3] {
+    unsafe fn set(self, location: GLint) {
+        gl::Uniform3fv(location, self.len() as GLsizei, self.as_ptr() as *const f32);
+    }
+}
+
+impl Uniform for &[Vec4] {
+    unsafe fn set(self, location: GLint) {
+        gl::Uniform4fv(location, self.len() as GLsizei, self.as_ptr() as *const f32);
+    }
+}
+
+impl Uniform for &[Mat3] {
+    unsafe fn set(self, location: GLint) {
+        gl::UniformMatrix3fv(location, self.len() as GLsizei, FALSE, self.as_ptr() as *const f32);
+    }
+}
+
+impl Uniform for &[Mat4] {
+    unsafe fn set(self, location: GLint) {
+        gl::UniformMatrix4fv(location, self.len() as GLsizei, FALSE, self.as_ptr() as *const f32);
+    }
+}
+
+impl<'a, T, const N: usize> Uniform for &'a [T; N] where &'a [T]: Uniform {
+    unsafe fn set(self, location: GLint) {
+        self.as_slice().set(location);
     }
 }
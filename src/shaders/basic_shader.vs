@@ -2,9 +2,9 @@
 layout (location = 0) in vec3 aPos;
 layout (location = 1) in vec4 color;
 
+#include "camera.glsl"
+
 uniform mat4 model;
-uniform mat4 view;
-uniform mat4 projection;
 
 out vec4 out_color;
 
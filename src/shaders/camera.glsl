@@ -0,0 +1,7 @@
+// Per frame camera data, uploaded once a frame by Scene::upload_frame_uniforms (CameraBlock in uniform_buffer.rs)
+layout (std140) uniform Camera {
+    mat4 projection;
+    mat4 view;
+    vec3 viewPos;
+    float time;     // seconds since the start
+};
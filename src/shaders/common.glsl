@@ -0,0 +1,132 @@
+// Shared by the lit fragment shaders: their inputs, the lights and their shadow maps, normal mapping and emissive maps.
+// MAX_LIGHTS, SHADOW_CASCADES and MAX_SHADOW_LAYERS are defined by the engine (see preprocessor.rs).
+
+#define DIRECTIONAL_LIGHT 0
+#define POINT_LIGHT 1
+#define SPOT_LIGHT 2
+
+in vec3 FragPos;
+in vec3 transformedNormal;
+in vec2 frag_texCoord;
+#ifdef USE_TEXTURE_ARRAY
+flat in float frag_layer;
+#endif
+in vec4 out_color;
+
+#include "camera.glsl"
+
+// the order packs the struct into 64 bytes (LightData in uniform_buffer.rs)
+struct Light {
+    vec3 position;
+    int type;
+    vec3 direction;
+    float innerCutoff;  // cosines of the spot cone angles
+    vec3 color;         // already multiplied by the intensity
+    float outerCutoff;
+    vec3 attenuation;   // constant, linear, quadratic
+    int shadowLayer;    // first layer in shadowMaps, -1 when the light casts no shadows
+};
+
+// uploaded once a frame like Camera (LightsBlock in uniform_buffer.rs)
+layout (std140) uniform Lights {
+    Light lights[MAX_LIGHTS];
+    int lightCount;
+    mat4 shadowMatrices[MAX_SHADOW_LAYERS];
+    float cascadeSplits[SHADOW_CASCADES];
+};
+
+uniform sampler2D normalMap;
+uniform sampler2D emissiveMap;
+uniform sampler2DArray shadowMaps;
+
+// 1 when fully lit, 0 when fully in shadow
+float shadowFactor(int i, vec3 norm, vec3 lightDir)
+{
+    int layer = lights[i].shadowLayer;
+    if (layer < 0){
+        return 1.0;
+    }
+
+    // directional lights have one layer per cascade, pick it from the distance to the camera
+    if (lights[i].type == DIRECTIONAL_LIGHT){
+        float depth = -(view * vec4(FragPos, 1.0)).z;
+        int cascade = SHADOW_CASCADES - 1;
+        for (int c = SHADOW_CASCADES - 1; c >= 0; c--){
+            if (depth < cascadeSplits[c]){
+                cascade = c;
+            }
+        }
+        layer += cascade;
+    }
+
+    vec4 lightSpacePos = shadowMatrices[layer] * vec4(FragPos, 1.0);
+    vec3 coords = lightSpacePos.xyz / lightSpacePos.w * 0.5 + 0.5;
+    if (coords.z > 1.0){
+        return 1.0;
+    }
+
+    // slope scaled bias against shadow acne
+    float bias = max(0.005 * (1.0 - dot(norm, lightDir)), 0.0005);
+
+    // PCF: average of a 3x3 block of depth comparisons
+    float shadow = 0.0;
+    vec2 texelSize = 1.0 / vec2(textureSize(shadowMaps, 0).xy);
+    for (int x = -1; x <= 1; x++){
+        for (int y = -1; y <= 1; y++){
+            float closest = texture(shadowMaps, vec3(coords.xy + vec2(x, y) * texelSize, layer)).r;
+            shadow += coords.z - bias > closest ? 1.0 : 0.0;
+        }
+    }
+    return 1.0 - shadow / 9.0;
+}
+
+// direction towards light i and how much of its color reaches the fragment: distance falloff,
+// spot cone and shadows
+float lightStrength(int i, vec3 norm, out vec3 lightDir)
+{
+    float strength = 1.0;
+
+    if (lights[i].type == DIRECTIONAL_LIGHT){
+        lightDir = normalize(-lights[i].direction);
+    }
+    else{
+        lightDir = normalize(lights[i].position - FragPos);
+
+        float distance = length(lights[i].position - FragPos);
+        vec3 att = lights[i].attenuation;
+        strength = 1.0 / (att.x + att.y * distance + att.z * distance * distance);
+
+        if (lights[i].type == SPOT_LIGHT){
+            float theta = dot(lightDir, normalize(-lights[i].direction));
+            strength *= smoothstep(lights[i].outerCutoff, lights[i].innerCutoff, theta);
+        }
+    }
+
+    return strength * shadowFactor(i, norm, lightDir);
+}
+
+// normal map lookup without tangent attributes: the tangent frame comes from the screen space
+// derivatives of the position and texture coordinates
+vec3 perturbNormal(vec3 norm)
+{
+    vec3 dp1 = dFdx(FragPos);
+    vec3 dp2 = dFdy(FragPos);
+    vec2 duv1 = dFdx(frag_texCoord);
+    vec2 duv2 = dFdy(frag_texCoord);
+
+    vec3 dp2perp = cross(dp2, norm);
+    vec3 dp1perp = cross(norm, dp1);
+    vec3 T = dp2perp * duv1.x + dp1perp * duv2.x;
+    vec3 B = dp2perp * duv1.y + dp1perp * duv2.y;
+    float invmax = inversesqrt(max(dot(T, T), dot(B, B)));
+    mat3 TBN = mat3(T * invmax, B * invmax, norm);
+
+    vec3 mapped = texture(normalMap, frag_texCoord).xyz * 2.0 - 1.0;
+    return normalize(TBN * mapped);
+}
+
+// the emissive color of the material, masked (and tinted) by the emissive map when there is one
+vec3 emission(vec3 emissive, bool useEmissiveMap)
+{
+    return useEmissiveMap ? emissive * texture(emissiveMap, frag_texCoord).rgb : emissive;
+}
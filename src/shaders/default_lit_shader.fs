@@ -1,16 +1,24 @@
 #version 330 core
 
+#include "common.glsl"
+
 out vec4 Result;
 
-in vec3 FragPos;
-in vec3 transformedNormal;
-in vec2 frag_texCoord;
-in vec4 out_color;
+struct Material {
+    vec4 albedo;
+    float specularStrength;
+    float shininess;
+    vec3 emissive;
+    bool useNormalMap;
+    bool useSpecularMap;    // scales specularStrength by its red channel
+    bool useEmissiveMap;
+    bool doubleSided;
+};
 
-uniform vec3 lightColor[32];
-uniform vec3 lightPos[32];
-uniform sampler2D textureSampler;
-uniform vec3 viewPos;
+uniform Material material;
+uniform sampler2D albedoTexture;
+uniform sampler2DArray albedoTextureArray;
+uniform sampler2D specularMap;
 
 void main()
 {
@@ -18,26 +26,45 @@ void main()
     vec3 diffuse = vec3(0.);
     vec3 specular = vec3(0.);
 
-    for (int i = 0; i < 32; i++){
+    vec3 norm = normalize(transformedNormal);
+    if (material.doubleSided && !gl_FrontFacing){
+        norm = -norm;
+    }
+    if (material.useNormalMap){
+        norm = perturbNormal(norm);
+    }
+    vec3 viewDir = normalize(viewPos - FragPos);
+
+    float specularStrength = material.specularStrength;
+    if (material.useSpecularMap){
+        specularStrength *= texture(specularMap, frag_texCoord).r;
+    }
+
+    for (int i = 0; i < lightCount; i++){
+        vec3 lightDir;
+        float strength = lightStrength(i, norm, lightDir);
+
         // Ambient
         float ambientStrength = 0.1;
-        ambient += lightColor[i] * ambientStrength;
+        ambient += lights[i].color * ambientStrength;
         
         // Diffuse
-        vec3 norm = normalize(transformedNormal);
-        vec3 lightDir = normalize(lightPos[i] - FragPos);
         float diff = max(dot(norm, lightDir), 0.0);
-        diffuse += lightColor[i] * diff;
+        diffuse += lights[i].color * diff * strength;
         
         // Specular
-        float specularStrength = 0.3;
-        vec3 viewDir = normalize(viewPos - FragPos);
         vec3 reflectDir = reflect(-lightDir, norm);  
-        float spec = pow(max(dot(viewDir, reflectDir), 0.0), 32.0);  // Specular exponent
-        specular += lightColor[i] * specularStrength * spec;  
+        float spec = pow(max(dot(viewDir, reflectDir), 0.0), material.shininess);
+        specular += lights[i].color * specularStrength * spec * strength;  
     }
 
     // Combine all lighting effects
-    vec4 texColor = texture(textureSampler, frag_texCoord);
-    Result = vec4(ambient + diffuse + specular, 1.0) * texColor * out_color;
+    vec4 albedo = material.albedo * out_color;
+#ifdef USE_TEXTURE
+    albedo *= texture(albedoTexture, frag_texCoord);
+#endif
+#ifdef USE_TEXTURE_ARRAY
+    albedo *= texture(albedoTextureArray, vec3(frag_texCoord, frag_layer));
+#endif
+    Result = vec4((ambient + diffuse) * albedo.rgb + specular + emission(material.emissive, material.useEmissiveMap), albedo.a);
 }
@@ -0,0 +1,24 @@
+#version 330 core
+layout (location = 0) in vec3 position;
+layout (location = 1) in vec4 color;
+layout (location = 2) in vec2 texCoord;
+layout (location = 3) in vec3 normal;
+
+// per instance attributes (a mat4 takes 4 attribute slots)
+layout (location = 4) in mat4 instanceModel;
+layout (location = 8) in vec4 instanceColor;
+layout (location = 9) in float instanceLayer;
+
+#include "transform.glsl"
+
+void main()
+{
+    transformVertex(instanceModel, position, normal);
+
+    // Pass color and texture coordinates
+    out_color = color * instanceColor;
+    frag_texCoord = texCoord;
+#ifdef USE_TEXTURE_ARRAY
+    frag_layer = instanceLayer;
+#endif
+}
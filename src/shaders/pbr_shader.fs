@@ -0,0 +1,153 @@
+#version 330 core
+
+#include "common.glsl"
+
+#define PI 3.14159265359
+
+out vec4 Result;
+
+// metallic-roughness workflow
+struct Material {
+    vec4 albedo;
+    float metallic;
+    float roughness;
+    float ao;
+    vec3 emissive;
+    bool useNormalMap;
+    bool useMetallicRoughnessMap;   // roughness in green, metallic in blue (glTF layout)
+    bool useAoMap;
+    bool useEmissiveMap;
+    bool doubleSided;
+};
+
+uniform Material material;
+uniform sampler2D albedoTexture;
+uniform sampler2DArray albedoTextureArray;
+uniform sampler2D metallicRoughnessMap;
+uniform sampler2D aoMap;
+
+// environment cube map used for image based lighting, its mipmaps stand in for
+// the prefiltered (blurrier for rougher surfaces) versions of it
+uniform samplerCube environmentMap;
+uniform bool useEnvironment;
+uniform float environmentIntensity;
+
+float distributionGGX(vec3 N, vec3 H, float roughness)
+{
+    float a = roughness * roughness;
+    float a2 = a * a;
+    float NdotH = max(dot(N, H), 0.0);
+    float denom = NdotH * NdotH * (a2 - 1.0) + 1.0;
+    return a2 / (PI * denom * denom);
+}
+
+float geometrySchlickGGX(float NdotV, float roughness)
+{
+    float r = roughness + 1.0;
+    float k = (r * r) / 8.0;
+    return NdotV / (NdotV * (1.0 - k) + k);
+}
+
+float geometrySmith(vec3 N, vec3 V, vec3 L, float roughness)
+{
+    return geometrySchlickGGX(max(dot(N, V), 0.0), roughness) * geometrySchlickGGX(max(dot(N, L), 0.0), roughness);
+}
+
+vec3 fresnelSchlick(float cosTheta, vec3 F0)
+{
+    return F0 + (1.0 - F0) * pow(clamp(1.0 - cosTheta, 0.0, 1.0), 5.0);
+}
+
+vec3 fresnelSchlickRoughness(float cosTheta, vec3 F0, float roughness)
+{
+    return F0 + (max(vec3(1.0 - roughness), F0) - F0) * pow(clamp(1.0 - cosTheta, 0.0, 1.0), 5.0);
+}
+
+// analytic fit of the split sum BRDF lookup table (Karis), saves precomputing it
+vec2 envBRDFApprox(float NdotV, float roughness)
+{
+    const vec4 c0 = vec4(-1.0, -0.0275, -0.572, 0.022);
+    const vec4 c1 = vec4(1.0, 0.0425, 1.04, -0.04);
+    vec4 r = roughness * c0 + c1;
+    float a004 = min(r.x * r.x, exp2(-9.28 * NdotV)) * r.x + r.y;
+    return vec2(-1.04, 1.04) * a004 + r.zw;
+}
+
+vec3 sampleEnvironment(vec3 dir, float lod)
+{
+    return textureLod(environmentMap, dir, lod).rgb;
+}
+
+void main()
+{
+    vec4 albedo = material.albedo * out_color;
+#ifdef USE_TEXTURE
+    albedo *= texture(albedoTexture, frag_texCoord);
+#endif
+#ifdef USE_TEXTURE_ARRAY
+    albedo *= texture(albedoTextureArray, vec3(frag_texCoord, frag_layer));
+#endif
+
+    float metallic = material.metallic;
+    float roughness = material.roughness;
+    if (material.useMetallicRoughnessMap){
+        vec4 mr = texture(metallicRoughnessMap, frag_texCoord);
+        roughness *= mr.g;
+        metallic *= mr.b;
+    }
+    roughness = clamp(roughness, 0.04, 1.0);
+
+    float ao = material.ao;
+    if (material.useAoMap){
+        ao *= texture(aoMap, frag_texCoord).r;
+    }
+
+    vec3 N = normalize(transformedNormal);
+    if (material.doubleSided && !gl_FrontFacing){
+        N = -N;
+    }
+    if (material.useNormalMap){
+        N = perturbNormal(N);
+    }
+    vec3 V = normalize(viewPos - FragPos);
+
+    // reflectance at normal incidence, dielectrics are around 0.04
+    vec3 F0 = mix(vec3(0.04), albedo.rgb, metallic);
+
+    vec3 Lo = vec3(0.0);
+    for (int i = 0; i < lightCount; i++){
+        vec3 L;
+        vec3 radiance = lights[i].color * lightStrength(i, N, L);
+
+        vec3 H = normalize(V + L);
+        float NDF = distributionGGX(N, H, roughness);
+        float G = geometrySmith(N, V, L, roughness);
+        vec3 F = fresnelSchlick(max(dot(H, V), 0.0), F0);
+
+        vec3 specular = NDF * G * F / (4.0 * max(dot(N, V), 0.0) * max(dot(N, L), 0.0) + 0.0001);
+        vec3 kD = (vec3(1.0) - F) * (1.0 - metallic);
+
+        float NdotL = max(dot(N, L), 0.0);
+        Lo += (kD * albedo.rgb / PI + specular) * radiance * NdotL;
+    }
+
+    vec3 ambient;
+    if (useEnvironment){
+        float NdotV = max(dot(N, V), 0.0);
+        vec3 F = fresnelSchlickRoughness(NdotV, F0, roughness);
+        vec3 kD = (1.0 - F) * (1.0 - metallic);
+
+        float maxLod = log2(float(textureSize(environmentMap, 0).x));
+        // the smallest mips are close enough to the irradiance for diffuse lighting
+        vec3 irradiance = sampleEnvironment(N, maxLod - 2.0);
+        vec3 prefiltered = sampleEnvironment(reflect(-V, N), roughness * (maxLod - 2.0));
+        vec2 brdf = envBRDFApprox(NdotV, roughness);
+
+        ambient = (kD * irradiance * albedo.rgb + prefiltered * (F * brdf.x + brdf.y)) * environmentIntensity * ao;
+    }
+    else{
+        ambient = vec3(0.03) * albedo.rgb * ao;
+    }
+
+    Result = vec4(ambient + Lo + emission(material.emissive, material.useEmissiveMap), albedo.a);
+}
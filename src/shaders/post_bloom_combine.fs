@@ -0,0 +1,14 @@
+#version 330 core
+out vec4 Result;
+
+in vec2 texCoord;
+
+uniform sampler2D screenTexture;
+uniform sampler2D bloomTexture;
+uniform float intensity;
+
+void main()
+{
+    vec4 color = texture(screenTexture, texCoord);
+    Result = vec4(color.rgb + texture(bloomTexture, texCoord).rgb * intensity, color.a);
+}
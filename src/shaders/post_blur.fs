@@ -0,0 +1,22 @@
+#version 330 core
+out vec4 Result;
+
+in vec2 texCoord;
+
+uniform sampler2D screenTexture;
+uniform vec2 texelSize;
+// (1, 0) or (0, 1), the blur is separable so it is done one axis at a time
+uniform vec2 direction;
+
+const float weights[5] = float[](0.227027, 0.1945946, 0.1216216, 0.054054, 0.016216);
+
+void main()
+{
+    vec2 offset = direction * texelSize;
+    vec3 color = texture(screenTexture, texCoord).rgb * weights[0];
+    for (int i = 1; i < 5; i++){
+        color += texture(screenTexture, texCoord + offset * float(i)).rgb * weights[i];
+        color += texture(screenTexture, texCoord - offset * float(i)).rgb * weights[i];
+    }
+    Result = vec4(color, 1.0);
+}
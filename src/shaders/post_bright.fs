@@ -0,0 +1,15 @@
+#version 330 core
+out vec4 Result;
+
+in vec2 texCoord;
+
+uniform sampler2D screenTexture;
+uniform float threshold;
+
+// keeps what is brighter than the threshold, fading in instead of cutting to avoid flickering
+void main()
+{
+    vec3 color = texture(screenTexture, texCoord).rgb;
+    float brightness = dot(color, vec3(0.2126, 0.7152, 0.0722));
+    Result = vec4(color * (max(brightness - threshold, 0.0) / max(brightness, 0.0001)), 1.0);
+}
@@ -0,0 +1,44 @@
+#version 330 core
+out vec4 Result;
+
+in vec2 texCoord;
+
+uniform sampler2D screenTexture;
+uniform vec2 texelSize;
+
+#define FXAA_REDUCE_MIN (1.0 / 128.0)
+#define FXAA_REDUCE_MUL (1.0 / 8.0)
+#define FXAA_SPAN_MAX 8.0
+
+// FXAA (Lottes), the simple variant: blur along the edge direction found from the luma of the corners
+void main()
+{
+    vec3 rgbNW = texture(screenTexture, texCoord + vec2(-1.0, -1.0) * texelSize).rgb;
+    vec3 rgbNE = texture(screenTexture, texCoord + vec2(1.0, -1.0) * texelSize).rgb;
+    vec3 rgbSW = texture(screenTexture, texCoord + vec2(-1.0, 1.0) * texelSize).rgb;
+    vec3 rgbSE = texture(screenTexture, texCoord + vec2(1.0, 1.0) * texelSize).rgb;
+    vec4 center = texture(screenTexture, texCoord);
+
+    vec3 toLuma = vec3(0.299, 0.587, 0.114);
+    float lumaNW = dot(rgbNW, toLuma);
+    float lumaNE = dot(rgbNE, toLuma);
+    float lumaSW = dot(rgbSW, toLuma);
+    float lumaSE = dot(rgbSE, toLuma);
+    float lumaM = dot(center.rgb, toLuma);
+    float lumaMin = min(lumaM, min(min(lumaNW, lumaNE), min(lumaSW, lumaSE)));
+    float lumaMax = max(lumaM, max(max(lumaNW, lumaNE), max(lumaSW, lumaSE)));
+
+    vec2 dir = vec2(-((lumaNW + lumaNE) - (lumaSW + lumaSE)), (lumaNW + lumaSW) - (lumaNE + lumaSE));
+    float dirReduce = max((lumaNW + lumaNE + lumaSW + lumaSE) * (0.25 * FXAA_REDUCE_MUL), FXAA_REDUCE_MIN);
+    float rcpDirMin = 1.0 / (min(abs(dir.x), abs(dir.y)) + dirReduce);
+    dir = clamp(dir * rcpDirMin, vec2(-FXAA_SPAN_MAX), vec2(FXAA_SPAN_MAX)) * texelSize;
+
+    vec3 rgbA = 0.5 * (texture(screenTexture, texCoord + dir * (1.0 / 3.0 - 0.5)).rgb +
+                       texture(screenTexture, texCoord + dir * (2.0 / 3.0 - 0.5)).rgb);
+    vec3 rgbB = rgbA * 0.5 + 0.25 * (texture(screenTexture, texCoord - dir * 0.5).rgb +
+                                     texture(screenTexture, texCoord + dir * 0.5).rgb);
+    float lumaB = dot(rgbB, toLuma);
+
+    // the wider sample went past the edge
+    Result = vec4((lumaB < lumaMin || lumaB > lumaMax) ? rgbA : rgbB, center.a);
+}
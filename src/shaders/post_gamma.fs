@@ -0,0 +1,13 @@
+#version 330 core
+out vec4 Result;
+
+in vec2 texCoord;
+
+uniform sampler2D screenTexture;
+uniform float gamma;
+
+void main()
+{
+    vec4 color = texture(screenTexture, texCoord);
+    Result = vec4(pow(max(color.rgb, 0.0), vec3(1.0 / gamma)), color.a);
+}
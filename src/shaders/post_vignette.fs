@@ -0,0 +1,16 @@
+#version 330 core
+out vec4 Result;
+
+in vec2 texCoord;
+
+uniform sampler2D screenTexture;
+uniform float strength;
+uniform float radius;
+
+void main()
+{
+    vec4 color = texture(screenTexture, texCoord);
+    // 0 inside the radius, fading to 1 towards the corners
+    float falloff = smoothstep(radius, radius + 0.45, distance(texCoord, vec2(0.5)));
+    Result = vec4(color.rgb * (1.0 - strength * falloff), color.a);
+}
@@ -0,0 +1,12 @@
+#version 330 core
+out vec4 FragColor;
+
+in vec3 direction;
+
+uniform samplerCube skybox;
+uniform float intensity;
+
+void main()
+{
+    FragColor = vec4(texture(skybox, direction).rgb * intensity, 1.0);
+}
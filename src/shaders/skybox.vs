@@ -0,0 +1,15 @@
+#version 330 core
+layout (location = 0) in vec3 aPos;
+
+#include "camera.glsl"
+
+out vec3 direction;
+
+void main()
+{
+    direction = aPos;
+    // only the rotation of the view, so the sky stays infinitely far away
+    vec4 position = projection * mat4(mat3(view)) * vec4(aPos, 1.0);
+    // z = w puts it on the far plane (depth 1), behind everything drawn before with depth test LEQUAL
+    gl_Position = position.xyww;
+}
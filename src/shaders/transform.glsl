@@ -0,0 +1,24 @@
+// Shared by the lit vertex shaders: the outputs common.glsl reads and the world space transform
+
+#include "camera.glsl"
+
+out vec3 FragPos;
+out vec3 transformedNormal; // Passing the transformed normal to the fragment shader
+out vec4 out_color;
+out vec2 frag_texCoord;
+#ifdef USE_TEXTURE_ARRAY
+flat out float frag_layer; // of the albedo texture array
+#endif
+
+// sets FragPos, transformedNormal and gl_Position for a vertex drawn with `model`
+void transformVertex(mat4 model, vec3 position, vec3 normal)
+{
+    // Transform the position to world space
+    FragPos = vec3(model * vec4(position, 1.));
+
+    // Normal transformation
+    transformedNormal = mat3(transpose(inverse(model))) * normal;
+
+    // Final position for rendering
+    gl_Position = projection * view * vec4(FragPos, 1.);
+}
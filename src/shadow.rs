@@ -0,0 +1,242 @@
+use std::{ptr, rc::Rc};
+
+use gl::{*, types::*};
+use glam::{vec3, vec4, Mat4, Vec3, Vec4Swizzles};
+
+use crate::{camera::Camera, game_object::GameObject, instanced::InstancedMesh, light::{Light, LightType, MAX_LIGHTS}, mesh::{get_model_matrix, Mesh}, resources, shader::Shader, transform::Transform};
+
+// given to every shader as #defines (see preprocessor::engine_defines)
+pub const SHADOW_CASCADES: usize = 3;
+pub const MAX_SHADOW_LAYERS: usize = 8;
+
+// Depth maps rendered from the shadow casting lights, all stored as layers of one texture array:
+// directional lights take SHADOW_CASCADES layers (one per slice of the camera frustum), spot lights one.
+// Point lights would need cube maps and don't cast shadows.
+pub struct ShadowMaps{
+    pub size: i32,
+    // how far from the camera directional light shadows are drawn
+    pub shadow_distance: f32,
+
+    pub fbo: u32,
+    pub texture: u32,
+
+    // light space matrix of every layer, and the first layer of each light (-1 when it has none)
+    pub matrices: Vec<Mat4>,
+    pub light_layers: Vec<i32>,
+    // view space depth where each cascade ends
+    pub cascade_splits: [f32; SHADOW_CASCADES],
+
+    depth_shader: Rc<Shader>,
+    instanced_depth_shader: Rc<Shader>,
+}
+
+impl ShadowMaps{
+    pub fn new(size: i32) -> Self{
+        ShadowMaps{
+            size,
+            shadow_distance: 40.,
+
+            fbo: 0,
+            texture: 0,
+
+            matrices: vec![],
+            light_layers: vec![],
+            cascade_splits: [0.; SHADOW_CASCADES],
+
+            depth_shader: resources::shader("src/shaders/shadow_depth.vs", "src/shaders/shadow_depth.fs"),
+            instanced_depth_shader: resources::shader("src/shaders/shadow_depth_instanced.vs", "src/shaders/shadow_depth.fs"),
+        }
+    }
+
+    pub fn setup(&mut self){
+        unsafe{
+            GenTextures(1, &mut self.texture);
+            BindTexture(TEXTURE_2D_ARRAY, self.texture);
+            TexImage3D(TEXTURE_2D_ARRAY, 0, DEPTH_COMPONENT24 as GLint, self.size, self.size, MAX_SHADOW_LAYERS as GLsizei, 0, DEPTH_COMPONENT, FLOAT, ptr::null());
+
+            TexParameteri(TEXTURE_2D_ARRAY, TEXTURE_MIN_FILTER, NEAREST as i32);
+            TexParameteri(TEXTURE_2D_ARRAY, TEXTURE_MAG_FILTER, NEAREST as i32);
+            // everything outside of the map is lit
+            TexParameteri(TEXTURE_2D_ARRAY, TEXTURE_WRAP_S, CLAMP_TO_BORDER as i32);
+            TexParameteri(TEXTURE_2D_ARRAY, TEXTURE_WRAP_T, CLAMP_TO_BORDER as i32);
+            let border = [1.0f32; 4];
+            TexParameterfv(TEXTURE_2D_ARRAY, TEXTURE_BORDER_COLOR, border.as_ptr());
+
+            GenFramebuffers(1, &mut self.fbo);
+            BindFramebuffer(FRAMEBUFFER, self.fbo);
+            // depth only
+            DrawBuffer(NONE);
+            ReadBuffer(NONE);
+            BindFramebuffer(FRAMEBUFFER, 0);
+        }
+    }
+
+    // Renders the depth of everything `draw_casters` draws into the layer of every shadow casting light.
+    // `w`/`h` are the size of the screen, used for fitting the cascades.
+    pub fn render(&mut self, lights: &[Light], camera: &Camera, w: f32, h: f32, mut draw_casters: impl FnMut(&ShadowPass)){
+        self.update_matrices(lights, camera, w, h);
+
+        unsafe{
+            // put back whatever was being drawn to (the window or the post process target) afterwards
+            let mut previous_framebuffer = 0;
+            let mut previous_viewport = [0; 4];
+            GetIntegerv(DRAW_FRAMEBUFFER_BINDING, &mut previous_framebuffer);
+            GetIntegerv(VIEWPORT, previous_viewport.as_mut_ptr());
+
+            BindFramebuffer(FRAMEBUFFER, self.fbo);
+            Viewport(0, 0, self.size, self.size);
+
+            for (layer, light_space) in self.matrices.iter().enumerate(){
+                FramebufferTextureLayer(FRAMEBUFFER, DEPTH_ATTACHMENT, self.texture, 0, layer as GLint);
+                Clear(DEPTH_BUFFER_BIT);
+
+                draw_casters(&ShadowPass { light_space: *light_space, shadow_maps: self });
+            }
+
+            BindFramebuffer(FRAMEBUFFER, previous_framebuffer as u32);
+            Viewport(previous_viewport[0], previous_viewport[1], previous_viewport[2], previous_viewport[3]);
+        }
+    }
+
+    fn update_matrices(&mut self, lights: &[Light], camera: &Camera, w: f32, h: f32){
+        self.matrices.clear();
+        self.light_layers.clear();
+
+        let near = 0.1;
+        let far = self.shadow_distance;
+        // mix of logarithmic and uniform splits, gives more resolution close to the camera
+        let lambda = 0.75;
+        for i in 0..SHADOW_CASCADES{
+            let t = (i + 1) as f32 / SHADOW_CASCADES as f32;
+            let logarithmic = near * (far / near).powf(t);
+            let uniform = near + (far - near) * t;
+            self.cascade_splits[i] = lambda * logarithmic + (1. - lambda) * uniform;
+        }
+
+        for light in lights.iter().take(MAX_LIGHTS){
+            let layers_needed = match light.kind{
+                LightType::Directional => SHADOW_CASCADES,
+                LightType::Spot => 1,
+                LightType::Point => 0,
+            };
+
+            if !light.cast_shadows || layers_needed == 0 || self.matrices.len() + layers_needed > MAX_SHADOW_LAYERS{
+                self.light_layers.push(-1);
+                continue;
+            }
+            self.light_layers.push(self.matrices.len() as i32);
+
+            match light.kind{
+                LightType::Directional => {
+                    let mut cascade_near = near;
+                    for i in 0..SHADOW_CASCADES{
+                        let matrix = cascade_matrix(light.direction, camera, w, h, cascade_near, self.cascade_splits[i]);
+                        self.matrices.push(matrix);
+                        cascade_near = self.cascade_splits[i];
+                    }
+                }
+                _ => {
+                    let view = Mat4::look_at_rh(light.position, light.position + light.direction, up_for(light.direction));
+                    let projection = Mat4::perspective_rh_gl((light.outer_angle * 2.).to_radians().min(179f32.to_radians()), 1., 0.1, 100.);
+                    self.matrices.push(projection * view);
+                }
+            }
+        }
+    }
+
+    // binds the maps to texture unit 1, their matrices go through the Lights block (see light::lights_block)
+    pub unsafe fn bind(&self){
+        ActiveTexture(TEXTURE1);
+        BindTexture(TEXTURE_2D_ARRAY, self.texture);
+        ActiveTexture(TEXTURE0);
+    }
+}
+
+impl Drop for ShadowMaps{
+    fn drop(&mut self){
+        unsafe{
+            if self.fbo != 0{
+                DeleteFramebuffers(1, &self.fbo);
+            }
+            if self.texture != 0{
+                DeleteTextures(1, &self.texture);
+            }
+        }
+    }
+}
+
+// Handed to the closure of ShadowMaps::render, draws depth only into the current layer
+pub struct ShadowPass<'a>{
+    pub light_space: Mat4,
+    shadow_maps: &'a ShadowMaps,
+}
+
+impl ShadowPass<'_>{
+    pub fn draw_mesh(&self, mesh: &Mesh, transform: Transform){
+        let shader = &self.shadow_maps.depth_shader;
+        unsafe{
+            shader.useProgram();
+            shader.set_uniform("lightSpace", self.light_space);
+            shader.set_uniform("model", get_model_matrix(transform));
+
+            BindVertexArray(mesh.vao);
+            DrawElements(TRIANGLES, mesh.indices.len() as i32, UNSIGNED_INT, ptr::null());
+            BindVertexArray(0);
+            UseProgram(0);
+        }
+    }
+
+    pub fn draw_object(&self, object: &GameObject<Mesh>){
+        if object.cast_shadows{
+            self.draw_mesh(&object.object, object.transform);
+        }
+    }
+
+    // the instances have to be uploaded already (see InstancedMesh::upload_instances)
+    pub fn draw_instanced(&self, instanced: &InstancedMesh){
+        if !instanced.cast_shadows || instanced.instance_count() == 0{
+            return;
+        }
+
+        let shader = &self.shadow_maps.instanced_depth_shader;
+        unsafe{
+            shader.useProgram();
+            shader.set_uniform("lightSpace", self.light_space);
+
+            BindVertexArray(instanced.vao);
+            DrawElementsInstanced(TRIANGLES, instanced.mesh.indices.len() as i32, UNSIGNED_INT, ptr::null(), instanced.instance_count() as i32);
+            BindVertexArray(0);
+            UseProgram(0);
+        }
+    }
+}
+
+// orthographic light space matrix that fits the slice of the camera frustum between `near` and `far`
+fn cascade_matrix(direction: Vec3, camera: &Camera, w: f32, h: f32, near: f32, far: f32) -> Mat4{
+    let projection = Mat4::perspective_rh_gl(camera.zoom.to_radians(), w / h, near, far);
+    let inverse = (projection * camera.get_view_matrix()).inverse();
+
+    let mut corners = vec![];
+    for x in [-1., 1.]{
+        for y in [-1., 1.]{
+            for z in [-1., 1.]{
+                let corner = inverse * vec4(x, y, z, 1.);
+                corners.push(corner.xyz() / corner.w);
+            }
+        }
+    }
+
+    let center = corners.iter().fold(Vec3::ZERO, |sum, corner| sum + *corner) / corners.len() as f32;
+    // fitting a sphere instead of a box keeps the size of the map constant while the camera turns
+    let radius = corners.iter().fold(0f32, |radius, corner| radius.max(corner.distance(center))).ceil();
+
+    let view = Mat4::look_at_rh(center - direction.normalize() * radius, center, up_for(direction));
+    // casters behind the slice (between it and the light) still have to end up in the map
+    let projection = Mat4::orthographic_rh_gl(-radius, radius, -radius, radius, -radius * 2., radius * 2.);
+
+    projection * view
+}
+
+fn up_for(direction: Vec3) -> Vec3{
+    if direction.normalize().dot(Vec3::Y).abs() > 0.99 {vec3(1., 0., 0.)} else {Vec3::Y}
+}
@@ -1,7 +1,8 @@
 use glam::{vec2, vec3, vec4, Vec2, Vec3, Vec4};
 
-use crate::{mesh::Mesh, shader::Shader, transform::Transform, vertex::Vertex};
+use crate::{mesh::Mesh, resources, transform::Transform, vertex::Vertex};
 
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
 pub enum Shapes{
     Circle,
     Quad,
@@ -64,7 +65,7 @@ pub fn make_shape(shape: Shapes, transform: Transform, color: Vec4) -> Mesh{
             indices.push(segments as u32);
             indices.push(1);
 
-            Mesh::new(vertices, indices, Shader::new("src/shaders/default_lit_shader.vs", "src/shaders/default_lit_shader.fs"))
+            Mesh::new(vertices, indices, resources::shader("src/shaders/default_lit_shader.vs", "src/shaders/default_lit_shader.fs"))
         }
 
         Shapes::Sphere => {
@@ -121,7 +122,7 @@ pub fn make_shape(shape: Shapes, transform: Transform, color: Vec4) -> Mesh{
                 }
             }
         
-            Mesh::new(vertices, indices, Shader::new("src/shaders/default_lit_shader.vs", "src/shaders/default_lit_shader.fs"))
+            Mesh::new(vertices, indices, resources::shader("src/shaders/default_lit_shader.vs", "src/shaders/default_lit_shader.fs"))
         }        
 
         Shapes::Quad => {
@@ -164,7 +165,7 @@ pub fn make_shape(shape: Shapes, transform: Transform, color: Vec4) -> Mesh{
                 2, 3, 0,  // Second triangle
             ];
 
-            Mesh::new(vertices, indices, Shader::new("src/shaders/default_lit_shader.vs", "src/shaders/default_lit_shader.fs"))
+            Mesh::new(vertices, indices, resources::shader("src/shaders/default_lit_shader.vs", "src/shaders/default_lit_shader.fs"))
         }
 
         Shapes::Cube => {
@@ -225,7 +226,7 @@ pub fn make_shape(shape: Shapes, transform: Transform, color: Vec4) -> Mesh{
                 20, 21, 22, 22, 23, 20,
             ];
 
-            Mesh::new(vertices, indices, Shader::new("src/shaders/default_lit_shader.vs", "src/shaders/default_lit_shader.fs"))
+            Mesh::new(vertices, indices, resources::shader("src/shaders/default_lit_shader.vs", "src/shaders/default_lit_shader.fs"))
         }
 
         Shapes::Triangle => {
@@ -264,7 +265,7 @@ pub fn make_shape(shape: Shapes, transform: Transform, color: Vec4) -> Mesh{
             indices.push(1);
             indices.push(2);
         
-            Mesh::new(vertices, indices, Shader::new("src/shaders/default_lit_shader.vs", "src/shaders/default_lit_shader.fs"))
+            Mesh::new(vertices, indices, resources::shader("src/shaders/default_lit_shader.vs", "src/shaders/default_lit_shader.fs"))
         }
 
         _ => {
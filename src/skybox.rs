@@ -0,0 +1,90 @@
+use std::{mem, rc::Rc};
+
+use gl::{*, types::*};
+
+use crate::{cubemap::Cubemap, resources, shader::Shader};
+
+const SKYBOX_VERTEX_SHADER: &str = "src/shaders/skybox.vs";
+const SKYBOX_FRAGMENT_SHADER: &str = "src/shaders/skybox.fs";
+
+// a unit cube seen from the inside, two triangles per face
+const CUBE_POSITIONS: [f32; 108] = [
+    -1.,  1., -1.,  -1., -1., -1.,   1., -1., -1.,   1., -1., -1.,   1.,  1., -1.,  -1.,  1., -1.,
+    -1., -1.,  1.,  -1., -1., -1.,  -1.,  1., -1.,  -1.,  1., -1.,  -1.,  1.,  1.,  -1., -1.,  1.,
+     1., -1., -1.,   1., -1.,  1.,   1.,  1.,  1.,   1.,  1.,  1.,   1.,  1., -1.,   1., -1., -1.,
+    -1., -1.,  1.,  -1.,  1.,  1.,   1.,  1.,  1.,   1.,  1.,  1.,   1., -1.,  1.,  -1., -1.,  1.,
+    -1.,  1., -1.,   1.,  1., -1.,   1.,  1.,  1.,   1.,  1.,  1.,  -1.,  1.,  1.,  -1.,  1., -1.,
+    -1., -1., -1.,  -1., -1.,  1.,   1., -1., -1.,   1., -1., -1.,  -1., -1.,  1.,   1., -1.,  1.,
+];
+
+// The background, drawn by the RenderQueue between the opaque and the transparent draws so only
+// the pixels nothing opaque covered are shaded. Share the cube map with Scene::environment to
+// have Pbr materials reflect it.
+pub struct Skybox{
+    pub cubemap: Rc<Cubemap>,
+    // multiplies the colors, for HDR skies that are too bright or dark
+    pub intensity: f32,
+
+    shader: Rc<Shader>,
+    vao: u32,
+    vbo: u32,
+}
+
+impl Skybox{
+    pub fn new(cubemap: Rc<Cubemap>) -> Self{
+        let (mut vao, mut vbo) = (0, 0);
+        unsafe{
+            GenVertexArrays(1, &mut vao);
+            GenBuffers(1, &mut vbo);
+
+            BindVertexArray(vao);
+            BindBuffer(ARRAY_BUFFER, vbo);
+            BufferData(ARRAY_BUFFER, mem::size_of_val(&CUBE_POSITIONS) as GLsizeiptr, CUBE_POSITIONS.as_ptr() as *const _, STATIC_DRAW);
+            EnableVertexAttribArray(0);
+            VertexAttribPointer(0, 3, FLOAT, FALSE, 3 * mem::size_of::<f32>() as GLsizei, std::ptr::null());
+            BindVertexArray(0);
+        }
+
+        Skybox{
+            cubemap,
+            intensity: 1.,
+
+            shader: resources::shader(SKYBOX_VERTEX_SHADER, SKYBOX_FRAGMENT_SHADER),
+            vao,
+            vbo,
+        }
+    }
+
+    // needs the frame uniforms (see Scene::upload_frame_uniforms) and the depth of the opaque draws
+    pub fn draw(&self){
+        unsafe{
+            DepthFunc(LEQUAL);
+            DepthMask(FALSE);
+            // seen from the inside, whatever the last material's culling was
+            Disable(CULL_FACE);
+
+            self.shader.useProgram();
+            self.shader.set_uniform("skybox", 0);
+            self.shader.set_uniform("intensity", self.intensity);
+            ActiveTexture(TEXTURE0);
+            BindTexture(TEXTURE_CUBE_MAP, self.cubemap.id);
+
+            BindVertexArray(self.vao);
+            DrawArrays(TRIANGLES, 0, 36);
+            BindVertexArray(0);
+
+            BindTexture(TEXTURE_CUBE_MAP, 0);
+            DepthMask(TRUE);
+            DepthFunc(LESS);
+        }
+    }
+}
+
+impl Drop for Skybox{
+    fn drop(&mut self){
+        unsafe{
+            DeleteVertexArrays(1, &self.vao);
+            DeleteBuffers(1, &self.vbo);
+        }
+    }
+}
@@ -0,0 +1,50 @@
+use glam::{vec4, Vec4};
+
+// Debug coloring of the simulation: constraints by how far they are from their rest length
+// (blue compressed -> green at rest -> red stretched) and particles by speed (blue slow -> red fast)
+pub struct StrainView{
+    pub enabled: bool,
+    // relative stretch/compression ((length - rest) / rest) that maps to full red/blue
+    pub max_strain: f32,
+    // speed (units per second) that maps to full red
+    pub max_speed: f32,
+}
+
+impl StrainView{
+    pub fn new() -> Self{
+        StrainView{
+            enabled: false,
+            max_strain: 0.5,
+            max_speed: 10.,
+        }
+    }
+
+    pub fn toggle(&mut self){
+        self.enabled = !self.enabled;
+    }
+
+    pub fn constraint_color(&self, length: f32, rest_length: f32) -> Vec4{
+        if rest_length <= 0.{
+            return gradient(0.);
+        }
+        let strain = (length - rest_length) / rest_length;
+        gradient(strain / self.max_strain)
+    }
+
+    pub fn particle_color(&self, speed: f32) -> Vec4{
+        gradient(speed / self.max_speed * 2. - 1.)
+    }
+}
+
+// -1 is blue, 0 is green and 1 is red, anything out of that range is clamped
+pub fn gradient(t: f32) -> Vec4{
+    let t = t.clamp(-1., 1.);
+    let green = vec4(0., 1., 0., 1.);
+
+    if t < 0.{
+        green.lerp(vec4(0., 0., 1., 1.), -t)
+    }
+    else{
+        green.lerp(vec4(1., 0., 0., 1.), t)
+    }
+}
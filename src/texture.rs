@@ -1,36 +1,213 @@
-use std::{ffi::c_void, path::Path};
+use std::{cell::Cell, ffi::CStr, path::Path};
 
-use gl::{types::{GLint, GLsizei, GLvoid}, GenerateMipmap, UNSIGNED_BYTE};
+use gl::{*, types::*};
+use image::{DynamicImage, ImageResult};
 
-pub fn make_tex(path: &str) -> u32{
-    let mut texture = 0;
+// from EXT_texture_filter_anisotropic (core only since 4.6), which almost every desktop driver has
+const TEXTURE_MAX_ANISOTROPY: GLenum = 0x84FE;
+const MAX_TEXTURE_MAX_ANISOTROPY: GLenum = 0x84FF;
 
-    unsafe{
-        let img = image::open(&Path::new(path)).expect("Failed to load texture");
-        let img_rgba = img.flipv().to_rgba8();
-        let data = img_rgba.as_raw();
+thread_local! {
+    // what the driver supports, 1 without the extension. Looked up the first time it is needed
+    static MAX_ANISOTROPY: Cell<Option<f32>> = const { Cell::new(None) };
+}
+
+// owns a GL texture, which is deleted from the GPU when dropped.
+// Share it between meshes with an Rc (see resources::texture) instead of copying the id around
+pub struct Texture{
+    pub id: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+impl Drop for Texture{
+    fn drop(&mut self){
+        unsafe{
+            gl::DeleteTextures(1, &self.id);
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Wrap{
+    Repeat,
+    MirroredRepeat,
+    ClampToEdge,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Filter{
+    Nearest,
+    Linear,
+}
+
+// How a texture is loaded and sampled. The default is what every texture used to get:
+// repeating, linear filtering, mipmaps generated but not sampled, flipped for GL and not sRGB.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct TextureDescriptor{
+    pub wrap: Wrap,
+    pub min_filter: Filter,
+    pub mag_filter: Filter,
+    pub mipmaps: bool,
+    // how the min filter picks between mip levels, None samples the full size level only.
+    // Ignored without mipmaps
+    pub mip_filter: Option<Filter>,
+    // samples taken at grazing angles, 1 is off. Clamped to what the driver supports
+    pub anisotropy: u32,
+    // for color textures authored on screen, so the shaders read linear values.
+    // Only 8 bit color images can be sRGB, it is ignored for the rest
+    pub srgb: bool,
+    // images start at the top row, GL textures at the bottom one
+    pub flip: bool,
+}
+
+impl Default for TextureDescriptor{
+    fn default() -> Self{
+        TextureDescriptor{
+            wrap: Wrap::Repeat,
+            min_filter: Filter::Linear,
+            mag_filter: Filter::Linear,
+            mipmaps: true,
+            mip_filter: None,
+            anisotropy: 1,
+            srgb: false,
+            flip: true,
+        }
+    }
+}
+
+impl TextureDescriptor{
+    // albedo and emissive textures, trilinear
+    pub fn color() -> Self{
+        TextureDescriptor { srgb: true, mip_filter: Some(Filter::Linear), anisotropy: 8, ..Default::default() }
+    }
+
+    // sharp pixels, no blending between them or mip levels
+    pub fn pixelated() -> Self{
+        TextureDescriptor { min_filter: Filter::Nearest, mag_filter: Filter::Nearest, mipmaps: false, ..Default::default() }
+    }
+}
 
+fn wrap_mode(wrap: Wrap) -> GLint{
+    (match wrap{
+        Wrap::Repeat => REPEAT,
+        Wrap::MirroredRepeat => MIRRORED_REPEAT,
+        Wrap::ClampToEdge => CLAMP_TO_EDGE,
+    }) as GLint
+}
+
+fn min_filter(descriptor: &TextureDescriptor) -> GLint{
+    let mip_filter = descriptor.mip_filter.filter(|_| descriptor.mipmaps);
+    (match (descriptor.min_filter, mip_filter){
+        (Filter::Nearest, None) => NEAREST,
+        (Filter::Linear, None) => LINEAR,
+        (Filter::Nearest, Some(Filter::Nearest)) => NEAREST_MIPMAP_NEAREST,
+        (Filter::Nearest, Some(Filter::Linear)) => NEAREST_MIPMAP_LINEAR,
+        (Filter::Linear, Some(Filter::Nearest)) => LINEAR_MIPMAP_NEAREST,
+        (Filter::Linear, Some(Filter::Linear)) => LINEAR_MIPMAP_LINEAR,
+    }) as GLint
+}
+
+// internal format, format, type and pixel data of an image. Grayscale stays in one (or two, with
+// alpha) channels and is swizzled back to gray, 16 bit and float (HDR) images keep their precision
+fn pixel_data(image: DynamicImage, srgb: bool) -> (GLenum, GLenum, GLenum, Vec<u8>, Option<[GLint; 4]>){
+    let gray = Some([RED as GLint, RED as GLint, RED as GLint, ONE as GLint]);
+    let gray_alpha = Some([RED as GLint, RED as GLint, RED as GLint, GREEN as GLint]);
+    let float_bytes = |pixels: Vec<f32>| pixels.into_iter().flat_map(f32::to_ne_bytes).collect();
+    let u16_bytes = |pixels: Vec<u16>| pixels.into_iter().flat_map(u16::to_ne_bytes).collect();
+
+    match image{
+        DynamicImage::ImageLuma8(image) => (R8, RED, UNSIGNED_BYTE, image.into_raw(), gray),
+        DynamicImage::ImageLumaA8(image) => (RG8, RG, UNSIGNED_BYTE, image.into_raw(), gray_alpha),
+        DynamicImage::ImageLuma16(image) => (R16, RED, UNSIGNED_SHORT, u16_bytes(image.into_raw()), gray),
+        DynamicImage::ImageLumaA16(image) => (RG16, RG, UNSIGNED_SHORT, u16_bytes(image.into_raw()), gray_alpha),
+        DynamicImage::ImageRgb16(_) | DynamicImage::ImageRgba16(_) => (RGBA16, RGBA, UNSIGNED_SHORT, u16_bytes(image.to_rgba16().into_raw()), None),
+        DynamicImage::ImageRgb32F(_) | DynamicImage::ImageRgba32F(_) => (RGBA16F, RGBA, FLOAT, float_bytes(image.to_rgba32f().into_raw()), None),
+        image => (if srgb {SRGB8_ALPHA8} else {RGBA8}, RGBA, UNSIGNED_BYTE, image.to_rgba8().into_raw(), None),
+    }
+}
+
+// the image file as the descriptor wants it on the GPU
+pub fn load_image(path: &str, descriptor: &TextureDescriptor) -> ImageResult<DynamicImage>{
+    let img = image::open(Path::new(path))?;
+    Ok(if descriptor.flip {img.flipv()} else {img})
+}
+
+// wrapping and filtering of the texture bound to `target`
+pub unsafe fn set_sampling(target: GLenum, descriptor: &TextureDescriptor){
+    gl::TexParameteri(target, gl::TEXTURE_WRAP_S, wrap_mode(descriptor.wrap));
+    gl::TexParameteri(target, gl::TEXTURE_WRAP_T, wrap_mode(descriptor.wrap));
+
+    gl::TexParameteri(target, gl::TEXTURE_MIN_FILTER, min_filter(descriptor));
+    gl::TexParameteri(target, gl::TEXTURE_MAG_FILTER, if descriptor.mag_filter == Filter::Nearest {NEAREST as GLint} else {LINEAR as GLint});
+
+    if descriptor.anisotropy > 1{
+        let max_anisotropy = max_anisotropy();
+        if max_anisotropy > 1.{
+            gl::TexParameterf(target, TEXTURE_MAX_ANISOTROPY, (descriptor.anisotropy as f32).min(max_anisotropy));
+        }
+    }
+}
+
+unsafe fn max_anisotropy() -> f32{
+    if let Some(max_anisotropy) = MAX_ANISOTROPY.get(){
+        return max_anisotropy;
+    }
+
+    let mut count = 0;
+    gl::GetIntegerv(NUM_EXTENSIONS, &mut count);
+    let supported = (0..count as u32).any(|i| {
+        let name = gl::GetStringi(EXTENSIONS, i);
+        !name.is_null() && matches!(CStr::from_ptr(name as *const _).to_bytes(), b"GL_EXT_texture_filter_anisotropic" | b"GL_ARB_texture_filter_anisotropic")
+    });
+
+    let mut max_anisotropy = 1.;
+    if supported{
+        gl::GetFloatv(MAX_TEXTURE_MAX_ANISOTROPY, &mut max_anisotropy);
+    }
+    else{
+        println!("No anisotropic filtering (GL_EXT_texture_filter_anisotropic), textures are sampled without it");
+    }
+    MAX_ANISOTROPY.set(Some(max_anisotropy));
+    max_anisotropy
+}
+
+// Loads an image file into a texture, see resources::texture for the cached version that
+// falls back to the default texture when loading fails
+pub fn make_tex(path: &str, descriptor: &TextureDescriptor) -> ImageResult<Texture>{
+    let img = load_image(path, descriptor)?;
+    let (width, height) = (img.width(), img.height());
+    let (internal_format, format, data_type, data, swizzle) = pixel_data(img, descriptor.srgb);
+
+    let mut texture = 0;
+    unsafe{
         gl::GenTextures(1, &mut texture);
         gl::BindTexture(gl::TEXTURE_2D, texture);
 
-        gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_WRAP_S, gl::REPEAT as i32);
-        gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_WRAP_T, gl::REPEAT as i32);
-        
-        gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MIN_FILTER, gl::LINEAR as i32);
-        gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MAG_FILTER, gl::LINEAR as i32);
+        set_sampling(gl::TEXTURE_2D, descriptor);
 
+        if let Some(swizzle) = swizzle{
+            gl::TexParameteriv(gl::TEXTURE_2D, TEXTURE_SWIZZLE_RGBA, swizzle.as_ptr());
+        }
+
+        // rows of one and two channel images aren't 4 byte aligned
+        gl::PixelStorei(gl::UNPACK_ALIGNMENT, 1);
         gl::TexImage2D(gl::TEXTURE_2D,
                        0,
-                       gl::RGBA as GLint,
-                       img.width() as GLsizei,
-                       img.height() as GLsizei,
+                       internal_format as GLint,
+                       width as GLsizei,
+                       height as GLsizei,
                        0,
-                       gl::RGBA,
-                       gl::UNSIGNED_BYTE,
-                       &data[0] as *const u8 as *const GLvoid
+                       format,
+                       data_type,
+                       data.as_ptr() as *const GLvoid
                        );
-        
-        GenerateMipmap(gl::TEXTURE_2D);
-        texture
+        gl::PixelStorei(gl::UNPACK_ALIGNMENT, 4);
+
+        if descriptor.mipmaps{
+            gl::GenerateMipmap(gl::TEXTURE_2D);
+        }
+        gl::BindTexture(gl::TEXTURE_2D, 0);
     }
-}
\ No newline at end of file
+    Ok(Texture { id: texture, width, height })
+}
@@ -0,0 +1,105 @@
+use gl::{*, types::*};
+use image::{imageops::{self, FilterType}, ImageResult, Rgba, RgbaImage};
+
+use crate::{resources::DEFAULT_TEXTURE, texture::{load_image, set_sampling, TextureDescriptor}};
+
+// Many same sized images in one GL_TEXTURE_2D_ARRAY, so objects drawn together (the instances of
+// an InstancedMesh) can each use a different one by picking a layer. Deleted from the GPU when dropped.
+pub struct TextureArray{
+    pub id: u32,
+    pub width: u32,
+    pub height: u32,
+    pub layers: u32,
+}
+
+impl Drop for TextureArray{
+    fn drop(&mut self){
+        unsafe{
+            DeleteTextures(1, &self.id);
+        }
+    }
+}
+
+// Collects the images of a TextureArray, loaded like make_tex does with the same descriptor for
+// all of them. Layers are 8 bit RGBA (sRGB if the descriptor says so) and images of another size
+// are stretched to the size of the array.
+pub struct TextureArrayBuilder{
+    width: u32,
+    height: u32,
+    descriptor: TextureDescriptor,
+    images: Vec<RgbaImage>,
+}
+
+impl TextureArrayBuilder{
+    pub fn new(width: u32, height: u32, descriptor: TextureDescriptor) -> Self{
+        TextureArrayBuilder{
+            width,
+            height,
+            descriptor,
+            images: vec![],
+        }
+    }
+
+    // returns the layer of the image, what instances pass to use it (see InstancedMesh::push_layer)
+    pub fn add(&mut self, path: &str) -> ImageResult<u32>{
+        let image = load_image(path, &self.descriptor)?.to_rgba8();
+        Ok(self.add_image(image))
+    }
+
+    // Like resources::texture_with: an image that fails to load is reported and the default
+    // texture takes its layer, so the layer numbers stay the same. Only panics if the default can't be loaded.
+    pub fn add_or_default(&mut self, path: &str) -> u32{
+        match self.add(path){
+            Ok(layer) => layer,
+            Err(error) if path == DEFAULT_TEXTURE => {
+                panic!("Failed to load the default texture {}: {}", path, error);
+            }
+            Err(error) => {
+                println!("Failed to load texture {}: {}\nfalling back to the default texture", path, error);
+                self.add_or_default(DEFAULT_TEXTURE)
+            }
+        }
+    }
+
+    pub fn add_image(&mut self, mut image: RgbaImage) -> u32{
+        if image.dimensions() != (self.width, self.height){
+            image = imageops::resize(&image, self.width, self.height, FilterType::Triangle);
+        }
+        self.images.push(image);
+        self.images.len() as u32 - 1
+    }
+
+    // An array without layers can't be allocated, so when no image was added (or every one
+    // failed to load) it gets a single white layer, which leaves the instance colors as they are
+    pub fn build(&self) -> TextureArray{
+        let white;
+        let images = if self.images.is_empty(){
+            white = [RgbaImage::from_pixel(self.width, self.height, Rgba([255; 4]))];
+            &white[..]
+        }
+        else{
+            &self.images[..]
+        };
+
+        let mut id = 0;
+        let layers = images.len() as u32;
+        unsafe{
+            GenTextures(1, &mut id);
+            BindTexture(TEXTURE_2D_ARRAY, id);
+            set_sampling(TEXTURE_2D_ARRAY, &self.descriptor);
+
+            let internal_format = if self.descriptor.srgb {SRGB8_ALPHA8} else {RGBA8};
+            TexImage3D(TEXTURE_2D_ARRAY, 0, internal_format as GLint, self.width as GLsizei, self.height as GLsizei, layers as GLsizei, 0, RGBA, UNSIGNED_BYTE, std::ptr::null());
+            for (layer, image) in images.iter().enumerate(){
+                TexSubImage3D(TEXTURE_2D_ARRAY, 0, 0, 0, layer as GLint, self.width as GLsizei, self.height as GLsizei, 1, RGBA, UNSIGNED_BYTE, image.as_ptr() as *const GLvoid);
+            }
+
+            if self.descriptor.mipmaps{
+                GenerateMipmap(TEXTURE_2D_ARRAY);
+            }
+            BindTexture(TEXTURE_2D_ARRAY, 0);
+        }
+
+        TextureArray { id, width: self.width, height: self.height, layers }
+    }
+}
@@ -0,0 +1,120 @@
+use std::{ffi::CString, marker::PhantomData, mem::size_of, ptr};
+
+use gl::*;
+use glam::{Mat4, Vec3};
+
+use crate::{light::MAX_LIGHTS, shadow::{MAX_SHADOW_LAYERS, SHADOW_CASCADES}};
+
+// binding points of the uniform blocks, every program gets its blocks attached to them after linking
+pub const CAMERA_BLOCK_BINDING: u32 = 0;
+pub const LIGHTS_BLOCK_BINDING: u32 = 1;
+
+const UNIFORM_BLOCKS: [(&str, u32); 2] = [
+    ("Camera", CAMERA_BLOCK_BINDING),
+    ("Lights", LIGHTS_BLOCK_BINDING),
+];
+
+// The structs below mirror the std140 layout of the blocks in the shaders:
+// vec3s take 16 bytes unless a scalar fills the last 4, and every element of a float array takes 16.
+
+// `Camera` in camera.glsl
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub struct CameraBlock{
+    pub projection: Mat4,
+    pub view: Mat4,
+    pub view_position: Vec3,
+    // seconds since the start
+    pub time: f32,
+}
+
+// one element of `lights[]` in common.glsl
+#[repr(C)]
+#[derive(Clone, Copy, Default)]
+pub struct LightData{
+    pub position: Vec3,
+    pub kind: i32,
+    pub direction: Vec3,
+    pub inner_cutoff: f32,
+    pub color: Vec3,
+    pub outer_cutoff: f32,
+    pub attenuation: Vec3,
+    pub shadow_layer: i32,
+}
+
+// `Lights` in common.glsl
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub struct LightsBlock{
+    pub lights: [LightData; MAX_LIGHTS],
+    pub light_count: i32,
+    _padding: [i32; 3],
+    pub shadow_matrices: [Mat4; MAX_SHADOW_LAYERS],
+    // only x is used
+    pub cascade_splits: [[f32; 4]; SHADOW_CASCADES],
+}
+
+const _: () = assert!(size_of::<CameraBlock>() == 144);
+const _: () = assert!(size_of::<LightData>() == 64);
+const _: () = assert!(size_of::<LightsBlock>() == 64 * MAX_LIGHTS + 16 + 64 * MAX_SHADOW_LAYERS + 16 * SHADOW_CASCADES);
+
+impl LightsBlock{
+    pub fn new() -> Self{
+        LightsBlock{
+            lights: [LightData::default(); MAX_LIGHTS],
+            light_count: 0,
+            _padding: [0; 3],
+            shadow_matrices: [Mat4::IDENTITY; MAX_SHADOW_LAYERS],
+            cascade_splits: [[0.; 4]; SHADOW_CASCADES],
+        }
+    }
+}
+
+// A buffer holding one T, shared by every program that declares the block bound to `binding`
+pub struct UniformBuffer<T>{
+    pub ubo: u32,
+    pub binding: u32,
+    data: PhantomData<T>,
+}
+
+impl<T: Copy> UniformBuffer<T>{
+    pub fn new(binding: u32) -> Self{
+        let mut ubo = 0;
+        unsafe{
+            GenBuffers(1, &mut ubo);
+            BindBuffer(UNIFORM_BUFFER, ubo);
+            BufferData(UNIFORM_BUFFER, size_of::<T>() as isize, ptr::null(), DYNAMIC_DRAW);
+            BindBuffer(UNIFORM_BUFFER, 0);
+        }
+        UniformBuffer { ubo, binding, data: PhantomData }
+    }
+
+    // replaces the content (orphaning the old storage) and binds the buffer to its binding point
+    pub fn upload(&self, value: &T){
+        unsafe{
+            BindBuffer(UNIFORM_BUFFER, self.ubo);
+            BufferData(UNIFORM_BUFFER, size_of::<T>() as isize, value as *const T as *const _, DYNAMIC_DRAW);
+            BindBuffer(UNIFORM_BUFFER, 0);
+            BindBufferBase(UNIFORM_BUFFER, self.binding, self.ubo);
+        }
+    }
+}
+
+impl<T> Drop for UniformBuffer<T>{
+    fn drop(&mut self){
+        unsafe{
+            DeleteBuffers(1, &self.ubo);
+        }
+    }
+}
+
+// attaches the blocks a freshly linked program declares to their binding points
+pub unsafe fn bind_blocks(program: u32){
+    for (name, binding) in UNIFORM_BLOCKS{
+        let name = CString::new(name).expect("error when binding uniform block");
+        let index = GetUniformBlockIndex(program, name.as_ptr());
+        if index != INVALID_INDEX{
+            UniformBlockBinding(program, index, binding);
+        }
+    }
+}
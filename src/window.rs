@@ -4,9 +4,14 @@ use gl::Enable;
 use glam::{vec2, vec3, Mat4, Vec2, Vec3};
 use glfw::{Action, Context, CursorMode, Glfw, GlfwReceiver, Key, MouseButton, PWindow, WindowEvent};
 
-use crate::camera::{Camera, PROJ_MATRIX};
+use crate::{camera::{Camera, PROJ_MATRIX}, postprocess::PostProcess};
+
+// samples of the offscreen target the scene is drawn into
+const MSAA_SAMPLES: i32 = 4;
 
 pub struct Window{
+    // first so it is dropped while the context (`window`) is still alive
+    pub post_process: PostProcess,
     pub w: u32,
     pub h: u32,
     pub window: PWindow,
@@ -18,7 +23,11 @@ pub struct Window{
     pub mouse_buttons: [bool; 8],
     pub mouse_scroll: [f32; 2],
     pub keyboard: HashMap<Key, Action>,
+    // the keyboard of the previous frame, for key_pressed
+    last_keyboard: HashMap<Key, Action>,
     pub dt: f32,
+    // when set, dt (and time) advance by this much every frame instead of the real frame time
+    pub fixed_timestep: Option<f32>,
     pub time: f32,
     last_time: Instant,
     pub camera: Camera,
@@ -53,9 +62,15 @@ impl Window{
 
         unsafe {
             Enable(gl::DEPTH_TEST);
+            // cube maps filter across their face edges, otherwise the seams show in the blurry mips
+            Enable(gl::TEXTURE_CUBE_MAP_SEAMLESS);
         }
 
+        let (fb_width, fb_height) = window.get_framebuffer_size();
+        let post_process = PostProcess::new(fb_width, fb_height, MSAA_SAMPLES);
+
         Window {
+            post_process,
             w,
             h,
             window,
@@ -66,8 +81,10 @@ impl Window{
             mouse_pos: Vec2::ZERO,
             mouse_buttons: [false; 8],
             mouse_scroll: [0.; 2],
+            last_keyboard: keyboard.clone(),
             keyboard,
             dt: 0.,
+            fixed_timestep: None,
             time: 0.,
             last_time: Instant::now(),
             camera: Camera::new(),
@@ -93,7 +110,7 @@ impl Window{
     pub fn update(&mut self){
         let current_time = Instant::now();
         let elapsed = current_time.duration_since(self.last_time);
-        self.dt = elapsed.as_secs_f32();
+        self.dt = self.fixed_timestep.unwrap_or(elapsed.as_secs_f32());
 
         self.last_time = current_time;
 
@@ -110,6 +127,7 @@ impl Window{
 
         self.mouse_scroll[0] = 0.;
         self.mouse_scroll[1] = 0.;
+        self.last_keyboard.clone_from(&self.keyboard);
         self.process_events();
 
         unsafe {
@@ -139,6 +157,7 @@ impl Window{
 
                         gl::Viewport(0, 0, width, height);
                     }
+                    self.post_process.resize(width, height);
                 }
 
                 glfw::WindowEvent::Key(key, _, action, _) => {
@@ -203,6 +222,32 @@ impl Window{
         }
     }
 
+    // everything drawn until end_scene goes through the post process effects
+    pub fn begin_scene(&self){
+        self.post_process.begin(self.clear_color);
+    }
+
+    pub fn end_scene(&self){
+        self.post_process.end();
+    }
+
+    // true only on the frame the key went down
+    pub fn key_pressed(&self, key: Key) -> bool{
+        self.keyboard[&key] == Action::Press && self.last_keyboard[&key] != Action::Press
+    }
+
+    // what the window currently shows, size in pixels
+    pub fn framebuffer_size(&self) -> (u32, u32){
+        let (width, height) = self.window.get_framebuffer_size();
+        (width as u32, height as u32)
+    }
+
+    // size in screen coordinates, what mouse_pos is in. Not the framebuffer size (w, h) on HiDPI displays
+    pub fn cursor_area(&self) -> Vec2{
+        let (width, height) = self.window.get_size();
+        vec2(width as f32, height as f32)
+    }
+
     pub fn set_caption(&mut self, caption: &str){
         self.window.set_title(caption);
     }